@@ -1,12 +1,13 @@
-use si_img::SiFont;
+use si_img::{SiError, SiFont};
 
 #[allow(unused)]
-fn main() {
+fn main() -> Result<(), SiError> {
     // From URL (synchronous)
     // For Async example, see examples/async/font.rs
     let font_from_url: SiFont = SiFont::from_network(
         "https://github.com/Zype-Z/ShareImage.js/raw/main/assets/fonts/sirin-stencil.ttf",
-    );
-    // From Vec
-    let font_from_url: SiFont = SiFont::from_vec(Vec::new());
+    )?;
+    // From Vec (fails, since an empty Vec is not a valid font)
+    let font_from_vec: Result<SiFont, SiError> = SiFont::from_vec(Vec::new());
+    Ok(())
 }
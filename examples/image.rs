@@ -2,13 +2,13 @@ use si_img::{SiFont, SiImage, TextOptions};
 
 use std::{fs, io::Write};
 
-fn main() {
+fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Create the image
-    let mut img = SiImage::from_network("https://res.cloudinary.com/zype/image/upload/regraphic");
+    let mut img = SiImage::from_network("https://res.cloudinary.com/zype/image/upload/regraphic")?;
     // Create the font
     let font = SiFont::from_network(
         "https://github.com/Zype-Z/ShareImage.js/raw/main/assets/fonts/sirin-stencil.ttf",
-    );
+    )?;
     // Render some text
     let text_options = TextOptions::default();
     // img.render_text("Hello, World!", 64.0, 480.0, 254.0, None, &font, &text_options);
@@ -25,7 +25,7 @@ fn main() {
             None,
             &font,
             &text_options,
-        )
+        )?
         .render_text(
             "Hello, Another!",
             48.0,
@@ -34,12 +34,13 @@ fn main() {
             None,
             &font,
             &text_options,
-        );
+        )?;
     // Write it
     let mut file = fs::OpenOptions::new()
         .create(true) // To create a new file
+        .truncate(true) // To overwrite an existing file
         .write(true) // To write
-        .open("out.png")
-        .unwrap();
-    file.write_all(&img.to_bytes()).unwrap();
+        .open("out.png")?;
+    file.write_all(&img.to_bytes()?)?;
+    Ok(())
 }
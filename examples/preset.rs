@@ -1,8 +1,8 @@
 use std::{collections::HashMap, fs, io::Write};
 
-use si_img::{SiFont, SiImage, SiPreset, TextOptions};
+use si_img::{SiError, SiFont, SiImage, SiPreset, TextOptions};
 
-fn main() {
+fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Create the preset
     let preset = SiPreset::new(Box::new(|img, vals| {
         let new_img = img.clone();
@@ -13,36 +13,32 @@ fn main() {
         let font = match vals.get("font") {
             Some(font) => {
                 // Do type checking
-                if font.type_id() == std::any::TypeId::of::<SiFont>() {
-                    // Downcast it
-                    font.downcast_ref::<SiFont>().unwrap()
-                } else {
-                    panic!(
-                        "Expected type: {:?}\nFound type: {:?}",
-                        std::any::TypeId::of::<SiFont>(),
-                        font.type_id()
-                    );
+                match font.downcast_ref::<SiFont>() {
+                    Some(font) => font,
+                    None => {
+                        return Err(SiError::PresetArgument(
+                            "Expected font to be of type SiFont".to_string(),
+                        ))
+                    }
                 }
             }
-            None => panic!("No font provided"),
+            None => return Err(SiError::PresetArgument("No font provided".to_string())),
         };
         // Render something on the image with that font
         // Get the title
         let title = match vals.get("title") {
             Some(title) => {
                 // Do type checking
-                if title.type_id() == std::any::TypeId::of::<String>() {
-                    // Downcast it
-                    title.downcast_ref::<String>().unwrap()
-                } else {
-                    panic!(
-                        "Expected type: {:?}\nFound type: {:?}",
-                        std::any::TypeId::of::<String>(),
-                        title.type_id()
-                    );
+                match title.downcast_ref::<String>() {
+                    Some(title) => title,
+                    None => {
+                        return Err(SiError::PresetArgument(
+                            "Expected title to be of type String".to_string(),
+                        ))
+                    }
                 }
             }
-            None => panic!("No title provided"),
+            None => return Err(SiError::PresetArgument("No title provided".to_string())),
         };
         let text_options = TextOptions::default();
         // Render it
@@ -51,23 +47,23 @@ fn main() {
 
     // Use it
     // Create the image
-    let mut img = SiImage::from_network("https://res.cloudinary.com/zype/image/upload/regraphic");
+    let mut img = SiImage::from_network("https://res.cloudinary.com/zype/image/upload/regraphic")?;
     // Create the font
     let font = SiFont::from_network(
         "https://github.com/Zype-Z/ShareImage.js/raw/main/assets/fonts/sirin-stencil.ttf",
-    );
+    )?;
     let font_val: Box<dyn std::any::Any> = Box::new(font);
     let title_val: Box<dyn std::any::Any> = Box::new("Hello, World!".to_string());
     let values: HashMap<String, Box<dyn std::any::Any>> = HashMap::from([
         ("font".to_string(), font_val),
         ("title".to_string(), title_val),
     ]);
-    img.load_preset(preset, values);
+    img.load_preset(&preset, values)?;
     let mut file = fs::OpenOptions::new()
         .create(true) // To create a new file
+        .truncate(true) // To overwrite an existing file
         .write(true)
-        // either use the ? operator or unwrap since it returns a Result
-        .open("out.png")
-        .unwrap();
-    file.write_all(&img.to_bytes()).unwrap();
+        .open("out.png")?;
+    file.write_all(&img.to_bytes()?)?;
+    Ok(())
 }
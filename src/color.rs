@@ -0,0 +1,501 @@
+use std::fmt;
+use std::str::FromStr;
+
+use image::Rgba;
+use wasm_bindgen::prelude::*;
+
+use crate::error::*;
+
+/// Represents a color, with an alpha channel.
+///
+/// Colors can be parsed from CSS syntax: hexadecimal (`#RGB`, `#RGBA`, `#RRGGBB` and
+/// `#RRGGBBAA`), `rgb()`/`rgba()`, `hsl()`/`hsla()` and named colors (e.g. `rebeccapurple`).
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SiColor {
+    /// The red channel.
+    pub r: u8,
+    /// The green channel.
+    pub g: u8,
+    /// The blue channel.
+    pub b: u8,
+    /// The alpha channel, from 0 (transparent) to 255 (opaque).
+    pub a: u8,
+}
+
+#[wasm_bindgen]
+impl SiColor {
+    /// Creates a new SiColor from its channels.
+    ///
+    /// # Arguments
+    ///
+    /// * `r` - The red channel.
+    /// * `g` - The green channel.
+    /// * `b` - The blue channel.
+    /// * `a` - The alpha channel, from 0 (transparent) to 255 (opaque).
+    #[wasm_bindgen(constructor)]
+    pub fn new(r: u8, g: u8, b: u8, a: u8) -> SiColor {
+        SiColor { r, g, b, a }
+    }
+
+    /// Parses a color in CSS syntax.
+    ///
+    /// # Arguments
+    ///
+    /// * `color` - The color, e.g. "#ff000080", "rgb(255 0 0 / 50%)", "hsl(0, 100%, 50%)"
+    ///   or "red".
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SiError::Color`] if the color is not valid.
+    #[wasm_bindgen]
+    pub fn parse(color: &str) -> Result<SiColor, SiError> {
+        let invalid = || SiError::Color(color.to_string());
+        let lower = color.trim().to_ascii_lowercase();
+        if let Some(hex) = lower.strip_prefix('#') {
+            return parse_hex(hex).ok_or_else(invalid);
+        }
+        if let Some((function, rest)) = lower.split_once('(') {
+            let args = rest.strip_suffix(')').ok_or_else(invalid)?;
+            return match function.trim_end() {
+                "rgb" | "rgba" => parse_rgb(args),
+                "hsl" | "hsla" => parse_hsl(args),
+                _ => None,
+            }
+            .ok_or_else(invalid);
+        }
+        named_color(&lower).ok_or_else(invalid)
+    }
+
+    /// Formats the color as `#RRGGBB`, or `#RRGGBBAA` if it isn't opaque.
+    #[wasm_bindgen(js_name = "toHex")]
+    pub fn to_hex(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl SiColor {
+    /// Opaque black, the default color of text.
+    pub const BLACK: SiColor = SiColor {
+        r: 0,
+        g: 0,
+        b: 0,
+        a: 255,
+    };
+    /// Opaque white.
+    pub const WHITE: SiColor = SiColor {
+        r: 255,
+        g: 255,
+        b: 255,
+        a: 255,
+    };
+    /// Fully transparent black.
+    pub const TRANSPARENT: SiColor = SiColor {
+        r: 0,
+        g: 0,
+        b: 0,
+        a: 0,
+    };
+}
+
+impl FromStr for SiColor {
+    type Err = SiError;
+
+    fn from_str(color: &str) -> Result<SiColor, SiError> {
+        SiColor::parse(color)
+    }
+}
+
+impl TryFrom<&str> for SiColor {
+    type Error = SiError;
+
+    fn try_from(color: &str) -> Result<SiColor, SiError> {
+        SiColor::parse(color)
+    }
+}
+
+impl fmt::Display for SiColor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)?;
+        if self.a != 255 {
+            write!(f, "{:02x}", self.a)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<SiColor> for Rgba<u8> {
+    fn from(color: SiColor) -> Rgba<u8> {
+        Rgba([color.r, color.g, color.b, color.a])
+    }
+}
+
+impl From<Rgba<u8>> for SiColor {
+    fn from(Rgba([r, g, b, a]): Rgba<u8>) -> SiColor {
+        SiColor { r, g, b, a }
+    }
+}
+
+/// Parses the digits of a hexadecimal color, with 3, 4, 6 or 8 digits.
+fn parse_hex(hex: &str) -> Option<SiColor> {
+    if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).ok().map(|d| d * 17);
+    let pair = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    match hex.len() {
+        3 => Some(SiColor::new(digit(0)?, digit(1)?, digit(2)?, 255)),
+        4 => Some(SiColor::new(digit(0)?, digit(1)?, digit(2)?, digit(3)?)),
+        6 => Some(SiColor::new(pair(0)?, pair(2)?, pair(4)?, 255)),
+        8 => Some(SiColor::new(pair(0)?, pair(2)?, pair(4)?, pair(6)?)),
+        _ => None,
+    }
+}
+
+/// Parses a color passed from JavaScript as a string in CSS syntax, if any.
+pub(crate) fn parse_optional(color: Option<String>) -> Result<Option<SiColor>, SiError> {
+    color.as_deref().map(SiColor::parse).transpose()
+}
+
+/// Splits the arguments of a color function, in either the legacy comma-separated syntax
+/// (`255, 0, 0, 0.5`) or the modern one (`255 0 0 / 50%`).
+fn split_args(args: &str) -> Option<Vec<&str>> {
+    let args: Vec<&str> = if args.contains(',') {
+        args.split(',').map(str::trim).collect()
+    } else {
+        let (channels, alpha) = match args.split_once('/') {
+            Some((channels, alpha)) => (channels, Some(alpha.trim())),
+            None => (args, None),
+        };
+        channels.split_whitespace().chain(alpha).collect()
+    };
+    (args.len() == 3 || args.len() == 4).then_some(args)
+}
+
+/// Parses a number, or a percentage of `max`.
+fn parse_number(arg: &str, max: f32) -> Option<f32> {
+    let value = match arg.strip_suffix('%') {
+        Some(percent) => percent.trim().parse::<f32>().ok()? / 100.0 * max,
+        None => arg.parse().ok()?,
+    };
+    value.is_finite().then_some(value)
+}
+
+/// Parses an alpha value, as a number from 0 to 1 or a percentage.
+fn parse_alpha(arg: Option<&&str>) -> Option<u8> {
+    match arg {
+        Some(arg) => Some((parse_number(arg, 1.0)?.clamp(0.0, 1.0) * 255.0).round() as u8),
+        None => Some(255),
+    }
+}
+
+/// Parses the arguments of `rgb()` or `rgba()`.
+fn parse_rgb(args: &str) -> Option<SiColor> {
+    let args = split_args(args)?;
+    let channel =
+        |i: usize| parse_number(args[i], 255.0).map(|value| value.clamp(0.0, 255.0).round() as u8);
+    Some(SiColor::new(
+        channel(0)?,
+        channel(1)?,
+        channel(2)?,
+        parse_alpha(args.get(3))?,
+    ))
+}
+
+/// Parses the arguments of `hsl()` or `hsla()`.
+fn parse_hsl(args: &str) -> Option<SiColor> {
+    let args = split_args(args)?;
+    let hue = match args[0] {
+        hue if hue.ends_with("deg") => hue.strip_suffix("deg")?.parse::<f32>().ok()?,
+        hue if hue.ends_with("turn") => hue.strip_suffix("turn")?.parse::<f32>().ok()? * 360.0,
+        hue if hue.ends_with("rad") => hue.strip_suffix("rad")?.parse::<f32>().ok()?.to_degrees(),
+        hue => hue.parse::<f32>().ok()?,
+    };
+    if !hue.is_finite() {
+        return None;
+    }
+    // Saturation and lightness are percentages, with or without the `%`
+    let percent = |arg: &str| {
+        let value = arg
+            .strip_suffix('%')
+            .unwrap_or(arg)
+            .trim()
+            .parse::<f32>()
+            .ok()?;
+        value.is_finite().then_some((value / 100.0).clamp(0.0, 1.0))
+    };
+    let saturation = percent(args[1])?;
+    let lightness = percent(args[2])?;
+
+    // https://www.w3.org/TR/css-color-4/#hsl-to-rgb
+    let channel = |n: f32| {
+        let k = (n + hue.rem_euclid(360.0) / 30.0) % 12.0;
+        let a = saturation * lightness.min(1.0 - lightness);
+        let value = lightness - a * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0);
+        (value * 255.0).round() as u8
+    };
+    Some(SiColor::new(
+        channel(0.0),
+        channel(8.0),
+        channel(4.0),
+        parse_alpha(args.get(3))?,
+    ))
+}
+
+/// Looks up a CSS named color.
+fn named_color(name: &str) -> Option<SiColor> {
+    if name == "transparent" {
+        return Some(SiColor::TRANSPARENT);
+    }
+    let index = NAMED_COLORS
+        .binary_search_by_key(&name, |(name, _)| name)
+        .ok()?;
+    let [r, g, b] = NAMED_COLORS[index].1;
+    Some(SiColor::new(r, g, b, 255))
+}
+
+/// The CSS named colors, sorted by name.
+const NAMED_COLORS: [(&str, [u8; 3]); 148] = [
+    ("aliceblue", [240, 248, 255]),
+    ("antiquewhite", [250, 235, 215]),
+    ("aqua", [0, 255, 255]),
+    ("aquamarine", [127, 255, 212]),
+    ("azure", [240, 255, 255]),
+    ("beige", [245, 245, 220]),
+    ("bisque", [255, 228, 196]),
+    ("black", [0, 0, 0]),
+    ("blanchedalmond", [255, 235, 205]),
+    ("blue", [0, 0, 255]),
+    ("blueviolet", [138, 43, 226]),
+    ("brown", [165, 42, 42]),
+    ("burlywood", [222, 184, 135]),
+    ("cadetblue", [95, 158, 160]),
+    ("chartreuse", [127, 255, 0]),
+    ("chocolate", [210, 105, 30]),
+    ("coral", [255, 127, 80]),
+    ("cornflowerblue", [100, 149, 237]),
+    ("cornsilk", [255, 248, 220]),
+    ("crimson", [220, 20, 60]),
+    ("cyan", [0, 255, 255]),
+    ("darkblue", [0, 0, 139]),
+    ("darkcyan", [0, 139, 139]),
+    ("darkgoldenrod", [184, 134, 11]),
+    ("darkgray", [169, 169, 169]),
+    ("darkgreen", [0, 100, 0]),
+    ("darkgrey", [169, 169, 169]),
+    ("darkkhaki", [189, 183, 107]),
+    ("darkmagenta", [139, 0, 139]),
+    ("darkolivegreen", [85, 107, 47]),
+    ("darkorange", [255, 140, 0]),
+    ("darkorchid", [153, 50, 204]),
+    ("darkred", [139, 0, 0]),
+    ("darksalmon", [233, 150, 122]),
+    ("darkseagreen", [143, 188, 143]),
+    ("darkslateblue", [72, 61, 139]),
+    ("darkslategray", [47, 79, 79]),
+    ("darkslategrey", [47, 79, 79]),
+    ("darkturquoise", [0, 206, 209]),
+    ("darkviolet", [148, 0, 211]),
+    ("deeppink", [255, 20, 147]),
+    ("deepskyblue", [0, 191, 255]),
+    ("dimgray", [105, 105, 105]),
+    ("dimgrey", [105, 105, 105]),
+    ("dodgerblue", [30, 144, 255]),
+    ("firebrick", [178, 34, 34]),
+    ("floralwhite", [255, 250, 240]),
+    ("forestgreen", [34, 139, 34]),
+    ("fuchsia", [255, 0, 255]),
+    ("gainsboro", [220, 220, 220]),
+    ("ghostwhite", [248, 248, 255]),
+    ("gold", [255, 215, 0]),
+    ("goldenrod", [218, 165, 32]),
+    ("gray", [128, 128, 128]),
+    ("green", [0, 128, 0]),
+    ("greenyellow", [173, 255, 47]),
+    ("grey", [128, 128, 128]),
+    ("honeydew", [240, 255, 240]),
+    ("hotpink", [255, 105, 180]),
+    ("indianred", [205, 92, 92]),
+    ("indigo", [75, 0, 130]),
+    ("ivory", [255, 255, 240]),
+    ("khaki", [240, 230, 140]),
+    ("lavender", [230, 230, 250]),
+    ("lavenderblush", [255, 240, 245]),
+    ("lawngreen", [124, 252, 0]),
+    ("lemonchiffon", [255, 250, 205]),
+    ("lightblue", [173, 216, 230]),
+    ("lightcoral", [240, 128, 128]),
+    ("lightcyan", [224, 255, 255]),
+    ("lightgoldenrodyellow", [250, 250, 210]),
+    ("lightgray", [211, 211, 211]),
+    ("lightgreen", [144, 238, 144]),
+    ("lightgrey", [211, 211, 211]),
+    ("lightpink", [255, 182, 193]),
+    ("lightsalmon", [255, 160, 122]),
+    ("lightseagreen", [32, 178, 170]),
+    ("lightskyblue", [135, 206, 250]),
+    ("lightslategray", [119, 136, 153]),
+    ("lightslategrey", [119, 136, 153]),
+    ("lightsteelblue", [176, 196, 222]),
+    ("lightyellow", [255, 255, 224]),
+    ("lime", [0, 255, 0]),
+    ("limegreen", [50, 205, 50]),
+    ("linen", [250, 240, 230]),
+    ("magenta", [255, 0, 255]),
+    ("maroon", [128, 0, 0]),
+    ("mediumaquamarine", [102, 205, 170]),
+    ("mediumblue", [0, 0, 205]),
+    ("mediumorchid", [186, 85, 211]),
+    ("mediumpurple", [147, 112, 219]),
+    ("mediumseagreen", [60, 179, 113]),
+    ("mediumslateblue", [123, 104, 238]),
+    ("mediumspringgreen", [0, 250, 154]),
+    ("mediumturquoise", [72, 209, 204]),
+    ("mediumvioletred", [199, 21, 133]),
+    ("midnightblue", [25, 25, 112]),
+    ("mintcream", [245, 255, 250]),
+    ("mistyrose", [255, 228, 225]),
+    ("moccasin", [255, 228, 181]),
+    ("navajowhite", [255, 222, 173]),
+    ("navy", [0, 0, 128]),
+    ("oldlace", [253, 245, 230]),
+    ("olive", [128, 128, 0]),
+    ("olivedrab", [107, 142, 35]),
+    ("orange", [255, 165, 0]),
+    ("orangered", [255, 69, 0]),
+    ("orchid", [218, 112, 214]),
+    ("palegoldenrod", [238, 232, 170]),
+    ("palegreen", [152, 251, 152]),
+    ("paleturquoise", [175, 238, 238]),
+    ("palevioletred", [219, 112, 147]),
+    ("papayawhip", [255, 239, 213]),
+    ("peachpuff", [255, 218, 185]),
+    ("peru", [205, 133, 63]),
+    ("pink", [255, 192, 203]),
+    ("plum", [221, 160, 221]),
+    ("powderblue", [176, 224, 230]),
+    ("purple", [128, 0, 128]),
+    ("rebeccapurple", [102, 51, 153]),
+    ("red", [255, 0, 0]),
+    ("rosybrown", [188, 143, 143]),
+    ("royalblue", [65, 105, 225]),
+    ("saddlebrown", [139, 69, 19]),
+    ("salmon", [250, 128, 114]),
+    ("sandybrown", [244, 164, 96]),
+    ("seagreen", [46, 139, 87]),
+    ("seashell", [255, 245, 238]),
+    ("sienna", [160, 82, 45]),
+    ("silver", [192, 192, 192]),
+    ("skyblue", [135, 206, 235]),
+    ("slateblue", [106, 90, 205]),
+    ("slategray", [112, 128, 144]),
+    ("slategrey", [112, 128, 144]),
+    ("snow", [255, 250, 250]),
+    ("springgreen", [0, 255, 127]),
+    ("steelblue", [70, 130, 180]),
+    ("tan", [210, 180, 140]),
+    ("teal", [0, 128, 128]),
+    ("thistle", [216, 191, 216]),
+    ("tomato", [255, 99, 71]),
+    ("turquoise", [64, 224, 208]),
+    ("violet", [238, 130, 238]),
+    ("wheat", [245, 222, 179]),
+    ("white", [255, 255, 255]),
+    ("whitesmoke", [245, 245, 245]),
+    ("yellow", [255, 255, 0]),
+    ("yellowgreen", [154, 205, 50]),
+];
+
+/// Decodes an sRGB channel to linear light, from 0 to 1.
+pub(crate) fn srgb_to_linear(channel: u8) -> f32 {
+    let c = channel as f32 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Encodes a linear light channel, from 0 to 1, as sRGB.
+pub(crate) fn linear_to_srgb(channel: f32) -> u8 {
+    let c = channel.clamp(0.0, 1.0);
+    let c = if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    };
+    (c * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(color: &str) -> String {
+        SiColor::parse(color).unwrap().to_hex()
+    }
+
+    #[test]
+    fn parses_hex_colors() {
+        assert_eq!(hex("#f80"), "#ff8800");
+        assert_eq!(hex("#f008"), "#ff000088");
+        assert_eq!(hex("#00FFff"), "#00ffff");
+        assert_eq!(hex("#11223344"), "#11223344");
+        for color in [
+            "#",
+            "#f",
+            "#ff",
+            "#12345",
+            "#1234567",
+            "#123456789",
+            "#ggg",
+            "#ÿÿÿ",
+            "00ffff",
+            "bad",
+            "face",
+        ] {
+            assert!(SiColor::parse(color).is_err(), "{}", color);
+        }
+    }
+
+    #[test]
+    fn parses_rgb_colors() {
+        assert_eq!(hex("rgb(255, 128, 0)"), "#ff8000");
+        assert_eq!(hex("rgba(255, 128, 0, 0.5)"), "#ff800080");
+        assert_eq!(hex("rgb(255 128 0)"), "#ff8000");
+        assert_eq!(hex("rgb(100% 50% 0% / 25%)"), "#ff800040");
+        assert_eq!(hex("RGB(300, -5, 0)"), "#ff0000");
+        for color in [
+            "rgb(1, 2)",
+            "rgb(1 2 3 4 5)",
+            "rgb(1, 2, 3",
+            "rgb(a b c)",
+            "rgb(1, 2, nan)",
+        ] {
+            assert!(SiColor::parse(color).is_err(), "{}", color);
+        }
+    }
+
+    #[test]
+    fn parses_hsl_colors() {
+        assert_eq!(hex("hsl(30, 50%, 60%)"), "#cc9966");
+        assert_eq!(hex("hsl(180deg 100% 50%)"), "#00ffff");
+        assert_eq!(hex("hsl(0.5turn, 100%, 25%)"), "#008080");
+        assert_eq!(hex("hsl(3.1415927rad, 100, 50)"), "#00ffff");
+        assert_eq!(hex("hsl(-120, 100%, 50%)"), "#0000ff");
+        assert_eq!(hex("hsla(240deg 100% 50% / 0.5)"), "#0000ff80");
+        for color in ["hsl(x, 1%, 1%)", "hsl(1, 1%)", "hsl(infdeg, 1%, 1%)"] {
+            assert!(SiColor::parse(color).is_err(), "{}", color);
+        }
+    }
+
+    #[test]
+    fn parses_named_colors() {
+        assert!(NAMED_COLORS.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert_eq!(hex("RebeccaPurple"), "#663399");
+        assert_eq!(hex(" teal "), "#008080");
+        assert_eq!(SiColor::parse("transparent").unwrap(), SiColor::TRANSPARENT);
+        assert!(matches!(SiColor::parse("nope"), Err(SiError::Color(_))));
+        assert!(matches!(SiColor::parse(""), Err(SiError::Color(_))));
+    }
+}
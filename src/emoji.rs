@@ -0,0 +1,151 @@
+use ab_glyph::GlyphId;
+use std::path::{Path, PathBuf};
+use ttf_parser::colr::{ClipBox, CompositeMode, Paint, Painter};
+use ttf_parser::{RgbaColor, Transform};
+
+/// Checks whether a grapheme is an emoji, or an emoji sequence.
+///
+/// A grapheme is an emoji if it contains a pictograph, a regional indicator (used for flags),
+/// an emoji presentation selector (U+FE0F) or a combining keycap (U+20E3).
+pub(crate) fn is_emoji(grapheme: &str) -> bool {
+    grapheme.chars().any(|char| {
+        matches!(
+            char as u32,
+            0x2300..=0x23FF | 0x2600..=0x27BF | 0x2B00..=0x2BFF | 0x1F000..=0x1FAFF | 0xFE0F | 0x20E3
+        )
+    })
+}
+
+/// Finds the image for an emoji in a directory of Twemoji-style PNGs.
+///
+/// Images are named after the code points of the emoji in lowercase hex, joined by `-`
+/// (e.g. `1f469-200d-1f4bb.png`). Since such sets usually leave out the emoji presentation
+/// selector (U+FE0F), a name without it is tried as well.
+pub(crate) fn find_emoji_image(dir: &Path, grapheme: &str) -> Option<PathBuf> {
+    let name = |chars: &mut dyn Iterator<Item = char>| {
+        chars
+            .map(|char| format!("{:x}", char as u32))
+            .collect::<Vec<String>>()
+            .join("-")
+    };
+    let full = name(&mut grapheme.chars());
+    let stripped = name(&mut grapheme.chars().filter(|char| *char != '\u{FE0F}'));
+    [full, stripped]
+        .iter()
+        .map(|name| dir.join(format!("{}.png", name)))
+        .find(|path| path.is_file())
+}
+
+/// A layer of a color glyph: the outline of a glyph, filled with a color.
+pub(crate) struct ColorLayer {
+    pub glyph: GlyphId,
+    pub color: [u8; 4],
+}
+
+/// Gets the layers of a glyph from the `COLR` table of a font.
+///
+/// Only glyphs made of outlines filled with solid colors (as in `COLR` version 0) are
+/// supported. Layers which use the foreground color are filled with `foreground`, the color
+/// of the text. For other glyphs, such as `COLR` version 1 glyphs with gradients, transforms
+/// or clips, `None` is returned so that they're drawn from a bitmap or as an outline instead.
+pub(crate) fn color_layers(
+    face: &ttf_parser::Face,
+    id: GlyphId,
+    foreground: [u8; 4],
+) -> Option<Vec<ColorLayer>> {
+    let id = ttf_parser::GlyphId(id.0);
+    if !face.is_color_glyph(id) {
+        return None;
+    }
+    let mut recorder = LayerRecorder {
+        outline: None,
+        layers: Vec::new(),
+        unsupported: false,
+    };
+    let [r, g, b, a] = foreground;
+    face.paint_color_glyph(id, 0, RgbaColor::new(r, g, b, a), &mut recorder)?;
+    (!recorder.unsupported).then_some(recorder.layers)
+}
+
+/// Records the layers of a color glyph as it is painted.
+struct LayerRecorder {
+    outline: Option<GlyphId>,
+    layers: Vec<ColorLayer>,
+    /// Whether the glyph uses anything besides outlines filled with solid colors.
+    unsupported: bool,
+}
+
+impl<'a> Painter<'a> for LayerRecorder {
+    fn outline_glyph(&mut self, glyph_id: ttf_parser::GlyphId) {
+        self.outline = Some(GlyphId(glyph_id.0));
+    }
+
+    fn paint(&mut self, paint: Paint<'a>) {
+        let (Paint::Solid(color), Some(glyph)) = (paint, self.outline) else {
+            self.unsupported = true;
+            return;
+        };
+        self.layers.push(ColorLayer {
+            glyph,
+            color: [color.red, color.green, color.blue, color.alpha],
+        });
+    }
+
+    fn push_clip(&mut self) {
+        self.unsupported = true;
+    }
+
+    fn push_clip_box(&mut self, _clipbox: ClipBox) {
+        self.unsupported = true;
+    }
+
+    fn pop_clip(&mut self) {}
+
+    fn push_layer(&mut self, _mode: CompositeMode) {
+        self.unsupported = true;
+    }
+
+    fn pop_layer(&mut self) {}
+
+    fn push_transform(&mut self, _transform: Transform) {
+        self.unsupported = true;
+    }
+
+    fn pop_transform(&mut self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty_recorder() -> LayerRecorder {
+        LayerRecorder {
+            outline: None,
+            layers: Vec::new(),
+            unsupported: false,
+        }
+    }
+
+    #[test]
+    fn solid_layers_are_recorded() {
+        let mut recorder = empty_recorder();
+        recorder.outline_glyph(ttf_parser::GlyphId(3));
+        recorder.paint(Paint::Solid(RgbaColor::new(255, 0, 0, 128)));
+        assert!(!recorder.unsupported);
+        assert_eq!(recorder.layers.len(), 1);
+        assert_eq!(recorder.layers[0].glyph, GlyphId(3));
+        assert_eq!(recorder.layers[0].color, [255, 0, 0, 128]);
+    }
+
+    #[test]
+    fn transforms_and_clips_are_unsupported() {
+        let mut recorder = empty_recorder();
+        recorder.push_transform(Transform::new_scale(2.0, 2.0));
+        assert!(recorder.unsupported);
+
+        let mut recorder = empty_recorder();
+        recorder.outline_glyph(ttf_parser::GlyphId(3));
+        recorder.push_clip();
+        assert!(recorder.unsupported);
+    }
+}
@@ -0,0 +1,64 @@
+use std::fmt;
+use wasm_bindgen::prelude::*;
+
+/// Represents an error raised by Si.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SiError {
+    /// The resource could not be fetched from the network.
+    Fetch(String),
+    /// The image data could not be decoded.
+    Decode(String),
+    /// The font data could not be parsed.
+    FontParse(String),
+    /// The image could not be encoded.
+    Encode(String),
+    /// A preset value was missing or had the wrong type.
+    PresetArgument(String),
+    /// The text didn't fit and the overflow policy is to fail.
+    Overflow(String),
+    /// A file could not be read.
+    Io(String),
+    /// No installed font matched the requested family.
+    FontNotFound(String),
+    /// A color could not be parsed.
+    Color(String),
+    /// An argument was out of range, such as a negative box width.
+    InvalidArgument(String),
+}
+
+impl fmt::Display for SiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SiError::Fetch(msg) => write!(f, "Could not fetch resource: {}", msg),
+            SiError::Decode(msg) => write!(f, "Could not decode image: {}", msg),
+            SiError::FontParse(msg) => write!(f, "Could not parse font: {}", msg),
+            SiError::Encode(msg) => write!(f, "Could not encode image: {}", msg),
+            SiError::PresetArgument(msg) => write!(f, "Invalid preset argument: {}", msg),
+            SiError::Overflow(msg) => write!(f, "Text overflowed: {}", msg),
+            SiError::Io(msg) => write!(f, "Could not read file: {}", msg),
+            SiError::FontNotFound(msg) => write!(f, "Could not find font: {}", msg),
+            SiError::Color(msg) => write!(f, "Invalid color: {}", msg),
+            SiError::InvalidArgument(msg) => write!(f, "Invalid argument: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for SiError {}
+
+impl From<SiError> for JsValue {
+    fn from(err: SiError) -> JsValue {
+        JsError::new(&err.to_string()).into()
+    }
+}
+
+impl From<std::io::Error> for SiError {
+    fn from(err: std::io::Error) -> SiError {
+        SiError::Io(err.to_string())
+    }
+}
+
+impl From<reqwest::Error> for SiError {
+    fn from(err: reqwest::Error) -> SiError {
+        SiError::Fetch(err.to_string())
+    }
+}
@@ -1,26 +1,195 @@
-use crate::TextOptions;
-use ab_glyph::{Font, FontVec, OutlinedGlyph};
+use crate::layout::{self, StyledText, TextLayout, TextStyle};
+use crate::{SiColor, SiError, TextOptions};
+use ab_glyph::{Font, FontVec, VariableFont};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use wasm_bindgen::prelude::*;
 
 /// Represents a font used for text rendering.
+///
+/// A SiFont is a font stack: a primary font followed by the fonts to fall back to, in order,
+/// for characters which the primary font doesn't contain.
 #[wasm_bindgen]
+#[derive(Clone)]
 pub struct SiFont {
-    pub(crate) font: FontVec,
+    pub(crate) fonts: Vec<Arc<StackFont>>,
+    pub(crate) emoji_dir: Option<PathBuf>,
 }
 
 #[wasm_bindgen]
 impl SiFont {
     /// Creates a new SiFont from a vector of font data.
     #[wasm_bindgen(constructor)]
-    pub fn new(vec: Vec<u8>) -> Self {
+    pub fn new(vec: Vec<u8>) -> Result<SiFont, SiError> {
         Self::from_vec(vec)
     }
 
     /// Creates a new SiFont from a vector of font data.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SiError::FontParse`] if the data is not a valid font.
+    #[wasm_bindgen]
+    pub fn from_vec(vec: Vec<u8>) -> Result<SiFont, SiError> {
+        Self::from_vec_with_index(vec, 0)
+    }
+
+    /// Creates a new SiFont from a face in a font collection (`.ttc` or `.otc`).
+    ///
+    /// The faces in a collection can be listed with [`SiFont::collection_faces`]. Fonts
+    /// which aren't collections only have the face at index 0.
+    ///
+    /// # Arguments
+    ///
+    /// * `vec` - The vector of font data.
+    /// * `index` - The index of the face in the collection.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SiError::FontParse`] if the data is not a valid font, or has no face at
+    /// `index`.
+    #[wasm_bindgen(js_name = "fromVecWithIndex")]
+    pub fn from_vec_with_index(vec: Vec<u8>, index: u32) -> Result<SiFont, SiError> {
+        let font = FontVec::try_from_vec_and_index(vec, index)
+            .map_err(|e| SiError::FontParse(e.to_string()))?;
+        Ok(SiFont {
+            fonts: vec![Arc::new(StackFont {
+                font,
+                index,
+                variations: Vec::new(),
+            })],
+            emoji_dir: None,
+        })
+    }
+
+    /// Lists the faces in a font collection (`.ttc` or `.otc`), or the single face of a font
+    /// which isn't a collection.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The font data.
+    ///
+    /// # Returns
+    ///
+    /// The index and names of each face, to pass to [`SiFont::from_vec_with_index`]
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SiError::FontParse`] if a face in the data is not a valid font.
+    #[wasm_bindgen(js_name = "collectionFaces")]
+    pub fn collection_faces(data: &[u8]) -> Result<Vec<FontFace>, SiError> {
+        let count = ttf_parser::fonts_in_collection(data).unwrap_or(1);
+        (0..count)
+            .map(|index| {
+                let face = ttf_parser::Face::parse(data, index)
+                    .map_err(|e| SiError::FontParse(e.to_string()))?;
+                Ok(FontFace {
+                    index,
+                    family: family_name(&face),
+                    style: face_name(&face, ttf_parser::name_id::TYPOGRAPHIC_SUBFAMILY)
+                        .or_else(|| face_name(&face, ttf_parser::name_id::SUBFAMILY)),
+                    full_name: face_name(&face, ttf_parser::name_id::FULL_NAME),
+                })
+            })
+            .collect()
+    }
+
+    /// Sets a variation axis of a variable font, such as its weight.
+    ///
+    /// The axis is set on every font in the stack which has it; the other fonts are left
+    /// as they are. Glyphs, advances and shaping all use the new axis value.
+    ///
+    /// # Arguments
+    ///
+    /// * `axis` - The four-letter tag of the axis (e.g. "wght", "wdth" or "slnt").
+    /// * `value` - The value of the axis, in the units of the axis (e.g. 700 for bold).
+    ///
+    /// # Returns
+    ///
+    /// A new SiFont, with the axis set
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SiError::InvalidArgument`] if the tag isn't four bytes long, or
+    /// [`SiError::FontParse`] if a font can't be parsed again with the new axis value.
+    #[wasm_bindgen(js_name = "withVariation")]
+    pub fn with_variation(&self, axis: &str, value: f32) -> Result<SiFont, SiError> {
+        let tag = <[u8; 4]>::try_from(axis.as_bytes()).map_err(|_| {
+            SiError::InvalidArgument(format!("axis tag must be four bytes long, got {:?}", axis))
+        })?;
+        let fonts = self
+            .fonts
+            .iter()
+            .map(|stack_font| {
+                if !stack_font.variations().iter().any(|a| a.tag == tag) {
+                    return Ok(stack_font.clone());
+                }
+                let mut variations: Vec<([u8; 4], f32)> = stack_font
+                    .variations
+                    .iter()
+                    .filter(|(t, _)| *t != tag)
+                    .cloned()
+                    .collect();
+                variations.push((tag, value));
+                // FontVec can't be cloned, so the font is parsed again with the new values
+                let index = stack_font.index;
+                let data = stack_font.as_slice().to_vec();
+                let mut font = FontVec::try_from_vec_and_index(data, index)
+                    .map_err(|e| SiError::FontParse(e.to_string()))?;
+                for (tag, value) in &variations {
+                    font.set_variation(tag, *value);
+                }
+                Ok(Arc::new(StackFont {
+                    font,
+                    index,
+                    variations,
+                }))
+            })
+            .collect::<Result<_, SiError>>()?;
+        Ok(SiFont {
+            fonts,
+            emoji_dir: self.emoji_dir.clone(),
+        })
+    }
+
+    /// Gets the variation axes of the primary font, which are empty for fonts which
+    /// aren't variable.
     #[wasm_bindgen]
-    pub fn from_vec(vec: Vec<u8>) -> SiFont {
-        let font = FontVec::try_from_vec(vec).unwrap();
-        SiFont { font }
+    pub fn axes(&self) -> Vec<FontAxis> {
+        self.primary()
+            .variations()
+            .into_iter()
+            .map(|axis| FontAxis {
+                tag: String::from_utf8_lossy(&axis.tag).into_owned(),
+                name: axis.name,
+                min: axis.min_value,
+                default: axis.default_value,
+                max: axis.max_value,
+            })
+            .collect()
+    }
+
+    /// Creates a font stack which falls back to another font.
+    ///
+    /// Each character is rendered with the first font in the stack which contains it.
+    ///
+    /// # Arguments
+    ///
+    /// * `fallback` - The font (or font stack) to use for characters missing from this one.
+    ///
+    /// # Returns
+    ///
+    /// A new SiFont, with the fonts of `fallback` after the fonts of this one
+    #[wasm_bindgen]
+    pub fn with_fallback(&self, fallback: &SiFont) -> SiFont {
+        SiFont {
+            fonts: self.fonts.iter().chain(&fallback.fonts).cloned().collect(),
+            emoji_dir: self
+                .emoji_dir
+                .clone()
+                .or_else(|| fallback.emoji_dir.clone()),
+        }
     }
 
     /// Creates a new SiFont from font data fetched from a network URL asynchronously.
@@ -28,24 +197,31 @@ impl SiFont {
     /// # Arguments
     ///
     /// * `url` - The URL from which to fetch the font data.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SiError::Fetch`] if the request fails, or [`SiError::FontParse`]
+    /// if the response is not a valid font.
     #[wasm_bindgen]
     #[cfg(feature = "async")]
-    pub async fn from_network_async(url: &str) -> Result<SiFont, JsValue> {
+    pub async fn from_network_async(url: &str) -> Result<SiFont, SiError> {
         let font_data: Vec<u8> = reqwest::get(url)
-            .await
-            .expect("Could not fetch font")
+            .await?
+            .error_for_status()?
             .bytes()
-            .await
-            .expect("Could not extract font")
+            .await?
             .into();
-        let font = FontVec::try_from_vec(font_data).unwrap();
-        Ok(SiFont { font })
+        Self::from_vec(font_data)
     }
 
     /// Placeholder method for when async feature is not enabled.
+    ///
+    /// # Errors
+    ///
+    /// Always returns [`SiError::Fetch`], since fetching requires the `async` feature.
     #[cfg(not(feature = "async"))]
-    pub fn from_network_async(_url: &str) {
-        panic!("async feature not enabled")
+    pub fn from_network_async(_url: &str) -> Result<SiFont, SiError> {
+        Err(SiError::Fetch(String::from("async feature not enabled")))
     }
 
     /// Creates a new SiFont from font data fetched from a network URL synchronously.
@@ -53,50 +229,460 @@ impl SiFont {
     /// # Arguments
     ///
     /// * `src` - The URL from which to fetch the font data.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SiError::Fetch`] if the request fails, or [`SiError::FontParse`]
+    /// if the response is not a valid font.
     #[cfg(feature = "blocking")]
-    pub fn from_network(src: &str) -> SiFont {
-        // Load font data from either URL or provided bytes.
-        let font_data: Vec<u8> = reqwest::blocking::get(src)
-            .expect("Could not fetch font")
-            .bytes()
-            .expect("Could not extract font")
+    pub fn from_network(src: &str) -> Result<SiFont, SiError> {
+        let font_data: Vec<u8> = reqwest::blocking::get(src)?
+            .error_for_status()?
+            .bytes()?
             .into();
-        let font = FontVec::try_from_vec(font_data).unwrap();
-        SiFont { font }
+        Self::from_vec(font_data)
     }
 
     /// Placeholder method for when blocking feature is not enabled.
+    ///
+    /// # Errors
+    ///
+    /// Always returns [`SiError::Fetch`], since fetching requires the `blocking` feature.
     #[cfg(not(feature = "blocking"))]
-    pub fn from_network(url: &str) {
-        panic!("blocking feature not enabled")
+    pub fn from_network(_url: &str) -> Result<SiFont, SiError> {
+        Err(SiError::Fetch(String::from("blocking feature not enabled")))
     }
 
-    pub(crate) fn layout(
+    /// Measures text without rendering it.
+    ///
+    /// The text is split into lines at explicit line breaks (`\n`), the same way
+    /// [`SiImage::render_text`](crate::SiImage::render_text) lays it out.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to measure.
+    /// * `scale` - The scale the text would be rendered at.
+    /// * `options` - The TextOptions the text would be laid out with.
+    ///
+    /// # Returns
+    ///
+    /// The size of the text
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SiError::Overflow`] if the text has more than `max_lines` lines and the
+    /// overflow policy is [`TextOverflow::Error`](crate::TextOverflow::Error).
+    #[wasm_bindgen]
+    pub fn measure(
         &self,
         text: &str,
         scale: f32,
-        position: Position,
         options: &TextOptions,
-    ) -> Vec<OutlinedGlyph> {
-        let mut res: Vec<OutlinedGlyph> = Vec::new();
-        let mut tmp_x: f32 = position.0;
-        for char in text.chars() {
-            if char.is_whitespace() {
-                tmp_x += options.space_width;
-            }
-            if let Some(glyph) = self.font.outline_glyph(
-                self.font
-                    .glyph_id(char)
-                    .with_scale_and_position(scale, ab_glyph::point(tmp_x, position.1)),
-            ) {
-                let bb = glyph.px_bounds();
-                res.push(glyph);
-                tmp_x += bb.width() + options.letter_spacing;
-                // tmp_y += bb.height();
+    ) -> Result<TextMetrics, SiError> {
+        let layout = self.layout(text, scale, SiColor::BLACK, None, None, options)?;
+        Ok(TextMetrics::from(&layout))
+    }
+
+    /// Finds the largest scale at which text fits inside a box.
+    ///
+    /// The text is wrapped to `max_width` as in
+    /// [`SiImage::render_text_box`](crate::SiImage::render_text_box). It fits when all of its
+    /// lines fit within `max_height` (and `max_lines`) without having to break any word in
+    /// the middle.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to fit.
+    /// * `min_scale` - The smallest scale to consider.
+    /// * `max_scale` - The largest scale to consider.
+    /// * `max_width` - The width of the box.
+    /// * `max_height` - The height of the box.
+    /// * `options` - The TextOptions the text would be laid out with.
+    ///
+    /// # Returns
+    ///
+    /// The chosen scale, or `min_scale` if the text doesn't fit even at that scale
+    #[wasm_bindgen]
+    pub fn fit_scale(
+        &self,
+        text: &str,
+        min_scale: f32,
+        max_scale: f32,
+        max_width: f32,
+        max_height: f32,
+        options: &TextOptions,
+    ) -> f32 {
+        let fits = |scale: f32| {
+            let words_fit = unicode_linebreak::linebreaks(text)
+                .scan(0, |start, (end, _)| {
+                    let word = text[*start..end].trim_end();
+                    *start = end;
+                    Some(word)
+                })
+                .all(|word| {
+                    let styles = [self.style(scale, SiColor::BLACK, options)];
+                    layout::layout_line(&StyledText::plain(word), &styles, options).width
+                        <= max_width
+                });
+            words_fit
+                && self
+                    .layout(
+                        text,
+                        scale,
+                        SiColor::BLACK,
+                        Some(max_width),
+                        Some(max_height),
+                        options,
+                    )
+                    .is_ok_and(|layout| !layout.truncated)
+        };
+        if fits(max_scale) {
+            return max_scale;
+        }
+        let (mut low, mut high) = (min_scale, max_scale);
+        // Scales are searched to within a hundredth of a pixel
+        while high - low > 0.01 {
+            let middle = (low + high) / 2.0;
+            if fits(middle) {
+                low = middle;
+            } else {
+                high = middle;
             }
         }
-        res
+        low
+    }
+
+    /// Gets the primary font, which line metrics are taken from.
+    pub(crate) fn primary(&self) -> &FontVec {
+        &self.fonts[0]
+    }
+
+    /// Gets the index of the first font in the stack which contains a character.
+    ///
+    /// Falls back to the primary font, which renders its "missing glyph" glyph.
+    pub(crate) fn font_for(&self, char: char) -> usize {
+        self.fonts
+            .iter()
+            .position(|font| font.glyph_id(char).0 != 0)
+            .unwrap_or(0)
+    }
+
+    /// Gets the style plain text is rendered with.
+    pub(crate) fn style(&self, scale: f32, color: SiColor, options: &TextOptions) -> TextStyle {
+        TextStyle {
+            font: self.clone(),
+            scale,
+            letter_spacing: options.letter_spacing,
+            color,
+        }
+    }
+
+    /// Lays out plain text into lines, positioned relative to the top-left corner of its box.
+    ///
+    /// See [`layout::layout`] for how the text is wrapped, truncated and aligned.
+    pub(crate) fn layout(
+        &self,
+        text: &str,
+        scale: f32,
+        color: SiColor,
+        max_width: Option<f32>,
+        max_height: Option<f32>,
+        options: &TextOptions,
+    ) -> Result<TextLayout, SiError> {
+        let styles = vec![self.style(scale, color, options)];
+        layout::layout(
+            &StyledText::plain(text),
+            styles,
+            max_width,
+            max_height,
+            options,
+        )
+    }
+}
+
+impl SiFont {
+    /// Creates a new SiFont from a font file.
+    /// **NOTE**: It doesn't work in WASM. Only for direct usage.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the font file.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SiError::Io`] if the file can't be read, or [`SiError::FontParse`]
+    /// if it is not a valid font.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<SiFont, SiError> {
+        Self::from_vec(std::fs::read(path)?)
+    }
+
+    /// Renders emoji from a directory of Twemoji-style PNGs.
+    /// **NOTE**: It doesn't work in WASM. Only for direct usage.
+    ///
+    /// Images are named after the code points of the emoji in lowercase hex, joined by `-`
+    /// (e.g. `1f469-200d-1f4bb.png` for 👩‍💻), and are scaled to the size of the text. Emoji
+    /// which aren't in the directory are rendered from the fonts in the stack, including
+    /// color fonts with `CBDT`, `sbix` or `COLR` tables.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - The directory containing the emoji images.
+    ///
+    /// # Returns
+    ///
+    /// A new SiFont, which renders emoji from the directory
+    pub fn with_emoji_dir(&self, dir: impl AsRef<Path>) -> SiFont {
+        SiFont {
+            fonts: self.fonts.clone(),
+            emoji_dir: Some(dir.as_ref().to_path_buf()),
+        }
+    }
+}
+
+/// A font in a font stack, along with the values its variation axes are set to.
+pub(crate) struct StackFont {
+    font: FontVec,
+    /// The index of the face in its font collection, or 0.
+    pub index: u32,
+    /// The tags and values of the axes which were set.
+    pub variations: Vec<([u8; 4], f32)>,
+}
+
+impl StackFont {
+    /// Parses the font with ttf-parser, with its variation axes set.
+    pub fn face(&self) -> Option<ttf_parser::Face<'_>> {
+        let mut face = ttf_parser::Face::parse(self.as_slice(), self.index).ok()?;
+        for (tag, value) in &self.variations {
+            face.set_variation(ttf_parser::Tag::from_bytes(tag), *value);
+        }
+        Some(face)
+    }
+
+    /// Parses the font with rustybuzz, with its variation axes set.
+    #[cfg(feature = "shaping")]
+    pub fn shaping_face(&self) -> Option<rustybuzz::Face<'_>> {
+        let mut face = rustybuzz::Face::from_slice(self.as_slice(), self.index)?;
+        let variations: Vec<rustybuzz::Variation> = self
+            .variations
+            .iter()
+            .map(|(tag, value)| rustybuzz::Variation {
+                tag: rustybuzz::ttf_parser::Tag::from_bytes(tag),
+                value: *value,
+            })
+            .collect();
+        face.set_variations(&variations);
+        Some(face)
+    }
+}
+
+impl Deref for StackFont {
+    type Target = FontVec;
+
+    fn deref(&self) -> &FontVec {
+        &self.font
+    }
+}
+
+/// Gets a name from the `name` table of a font, in the first encoding which can be read.
+pub(crate) fn face_name(face: &ttf_parser::Face, id: u16) -> Option<String> {
+    face.names()
+        .into_iter()
+        .filter(|name| name.name_id == id)
+        .find_map(|name| name.to_string())
+}
+
+/// Gets the family name of a font, preferring the typographic family name.
+pub(crate) fn family_name(face: &ttf_parser::Face) -> Option<String> {
+    face_name(face, ttf_parser::name_id::TYPOGRAPHIC_FAMILY)
+        .or_else(|| face_name(face, ttf_parser::name_id::FAMILY))
+}
+
+/// A face in a font collection, as returned by [`SiFont::collection_faces`].
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct FontFace {
+    /// The index of the face in the collection.
+    pub index: u32,
+    /// The family name of the face (e.g. "Noto Sans CJK JP").
+    #[wasm_bindgen(getter_with_clone)]
+    pub family: Option<String>,
+    /// The style name of the face (e.g. "Bold").
+    #[wasm_bindgen(getter_with_clone)]
+    pub style: Option<String>,
+    /// The full name of the face (e.g. "Noto Sans CJK JP Bold").
+    #[wasm_bindgen(getter_with_clone)]
+    pub full_name: Option<String>,
+}
+
+/// A variation axis of a variable font, as returned by [`SiFont::axes`].
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct FontAxis {
+    /// The four-letter tag of the axis (e.g. "wght").
+    #[wasm_bindgen(getter_with_clone)]
+    pub tag: String,
+    /// The name of the axis (e.g. "Weight").
+    #[wasm_bindgen(getter_with_clone)]
+    pub name: Option<String>,
+    /// The smallest value of the axis.
+    pub min: f32,
+    /// The default value of the axis.
+    pub default: f32,
+    /// The largest value of the axis.
+    pub max: f32,
+}
+
+/// Represents the variants of a font family, such as its bold and italic fonts.
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct SiFontFamily {
+    regular: SiFont,
+    bold: Option<SiFont>,
+    italic: Option<SiFont>,
+    bold_italic: Option<SiFont>,
+}
+
+#[wasm_bindgen]
+impl SiFontFamily {
+    /// Creates a new SiFontFamily from its regular font.
+    ///
+    /// # Arguments
+    ///
+    /// * `regular` - The font used for text which is neither bold nor italic.
+    #[wasm_bindgen(constructor)]
+    pub fn new(regular: &SiFont) -> SiFontFamily {
+        SiFontFamily {
+            regular: regular.clone(),
+            bold: None,
+            italic: None,
+            bold_italic: None,
+        }
+    }
+
+    /// Sets the bold font of the family.
+    ///
+    /// # Returns
+    ///
+    /// A new SiFontFamily, with the bold font set
+    #[wasm_bindgen(js_name = "withBold")]
+    pub fn with_bold(&self, font: &SiFont) -> SiFontFamily {
+        SiFontFamily {
+            bold: Some(font.clone()),
+            ..self.clone()
+        }
+    }
+
+    /// Sets the italic font of the family.
+    ///
+    /// # Returns
+    ///
+    /// A new SiFontFamily, with the italic font set
+    #[wasm_bindgen(js_name = "withItalic")]
+    pub fn with_italic(&self, font: &SiFont) -> SiFontFamily {
+        SiFontFamily {
+            italic: Some(font.clone()),
+            ..self.clone()
+        }
+    }
+
+    /// Sets the bold italic font of the family.
+    ///
+    /// # Returns
+    ///
+    /// A new SiFontFamily, with the bold italic font set
+    #[wasm_bindgen(js_name = "withBoldItalic")]
+    pub fn with_bold_italic(&self, font: &SiFont) -> SiFontFamily {
+        SiFontFamily {
+            bold_italic: Some(font.clone()),
+            ..self.clone()
+        }
+    }
+
+    /// Gets the font for a variant of the family.
+    ///
+    /// Variants which weren't set fall back to the closest one which was: bold italic falls
+    /// back to bold, then italic, and everything falls back to regular.
+    ///
+    /// # Arguments
+    ///
+    /// * `bold` - Whether the text is bold.
+    /// * `italic` - Whether the text is italic.
+    #[wasm_bindgen]
+    pub fn variant(&self, bold: bool, italic: bool) -> SiFont {
+        let font = match (bold, italic) {
+            (true, true) => self
+                .bold_italic
+                .as_ref()
+                .or(self.bold.as_ref())
+                .or(self.italic.as_ref()),
+            (true, false) => self.bold.as_ref(),
+            (false, true) => self.italic.as_ref(),
+            (false, false) => None,
+        };
+        font.unwrap_or(&self.regular).clone()
+    }
+}
+
+/// The size of a piece of text, as measured by [`SiFont::measure`].
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct TextMetrics {
+    /// The width of the widest line.
+    pub width: f32,
+    /// The distance from the top of the first line to the bottom of the last line.
+    pub height: f32,
+    /// The distance from the baseline to the top of a line.
+    pub ascent: f32,
+    /// The distance from the baseline to the bottom of a line (usually negative).
+    pub descent: f32,
+    /// The number of lines.
+    pub line_count: usize,
+    line_widths: Vec<f32>,
+}
+
+#[wasm_bindgen]
+impl TextMetrics {
+    /// Gets the width of each line.
+    #[wasm_bindgen(getter)]
+    pub fn line_widths(&self) -> Vec<f32> {
+        self.line_widths.clone()
+    }
+}
+
+impl From<&TextLayout> for TextMetrics {
+    fn from(layout: &TextLayout) -> TextMetrics {
+        TextMetrics {
+            width: layout
+                .lines
+                .iter()
+                .map(|line| line.width)
+                .fold(0.0, f32::max),
+            height: layout.height,
+            ascent: layout.ascent,
+            descent: layout.descent,
+            line_count: layout.lines.len(),
+            line_widths: layout.lines.iter().map(|line| line.width).collect(),
+        }
     }
 }
 
 pub type Position = (f32, f32);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_variation_tags_are_rejected() {
+        let font =
+            SiFont::from_vec(include_bytes!("../tests/fonts/DejaVuSansMono.ttf").to_vec()).unwrap();
+        for tag in ["", "wg", "wghts", "wgh\u{e9}"] {
+            assert!(matches!(
+                font.with_variation(tag, 700.0),
+                Err(SiError::InvalidArgument(_))
+            ));
+        }
+        // Fonts without the axis are left as they are
+        assert!(font.with_variation("wght", 700.0).is_ok());
+    }
+}
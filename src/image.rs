@@ -1,27 +1,329 @@
-use ab_glyph::{Font, ScaleFont};
+use ab_glyph::{Font, GlyphImageFormat, OutlinedGlyph, ScaleFont};
 use image::{
-    imageops::{overlay, resize},
-    DynamicImage, GenericImage, GenericImageView, Rgb, Rgba,
+    imageops::resize, DynamicImage, GenericImage, GenericImageView, Rgb, Rgba, Rgba32FImage,
+    RgbaImage,
 };
 use wasm_bindgen::prelude::*;
 
+use crate::color::*;
+use crate::emoji;
+use crate::error::*;
 use crate::font::*;
+use crate::layout::TextLayout;
+use crate::mask::Mask;
+use crate::paint::*;
 use crate::preset::*;
+use crate::rich::*;
 
+/// Horizontal alignment of text.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextAlign {
+    /// Lines start at the left edge.
+    Left,
+    /// Lines are centered.
+    Center,
+    /// Lines end at the right edge.
+    Right,
+    /// Wrapped lines are stretched to fill the width, the rest are aligned to the start.
+    Justify,
+    /// Lines are aligned to the left edge for left-to-right text, or the right edge for
+    /// right-to-left text.
+    Start,
+    /// Lines are aligned to the right edge for left-to-right text, or the left edge for
+    /// right-to-left text.
+    End,
+}
+
+/// Base direction of text.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextDirection {
+    /// Uses the direction of the first strong character in the text.
+    Auto,
+    /// Left-to-right.
+    Ltr,
+    /// Right-to-left.
+    Rtl,
+}
+
+/// Vertical alignment of text.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VerticalAlign {
+    /// The top of the first line is at the top edge.
+    Top,
+    /// The text is centered.
+    Middle,
+    /// The bottom of the last line is at the bottom edge.
+    Bottom,
+    /// The baseline of the first line is at the top edge.
+    Baseline,
+}
+
+/// What to do with text which doesn't fit.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextOverflow {
+    /// Lines which don't fit are not rendered.
+    Clip,
+    /// Lines which don't fit are not rendered, and the last rendered line ends with an ellipsis.
+    Ellipsis,
+    /// Rendering fails with [`SiError::Overflow`].
+    Error,
+}
+
+/// Where a stroke is drawn relative to the outline of the glyphs.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StrokePosition {
+    /// The stroke is drawn outside of the glyphs.
+    Outside,
+    /// The stroke is drawn inside of the glyphs.
+    Inside,
+    /// The stroke is centered on the outline of the glyphs.
+    Center,
+}
+
+/// How colors are blended with the colors of the image under them.
+///
+/// The modes follow the [Compositing and Blending](https://www.w3.org/TR/compositing-1/#blending)
+/// specification, as used by CSS and design tools.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlendMode {
+    /// The color is drawn over the image.
+    Normal,
+    /// The colors are multiplied, which always darkens.
+    Multiply,
+    /// The inverted colors are multiplied, which always lightens.
+    Screen,
+    /// Multiplies or screens, depending on the color of the image.
+    Overlay,
+    /// The darker of the colors is kept.
+    Darken,
+    /// The lighter of the colors is kept.
+    Lighten,
+    /// Darkens or lightens, depending on the color drawn, like a diffused spotlight.
+    SoftLight,
+    /// The image is brightened to reflect the color drawn.
+    ColorDodge,
+    /// The darker color is subtracted from the lighter one.
+    Difference,
+}
+
+impl BlendMode {
+    /// Blends a channel of a color drawn over a channel of the image, from 0 to 1.
+    fn blend(self, base: f32, overlay: f32) -> f32 {
+        match self {
+            BlendMode::Normal => overlay,
+            BlendMode::Multiply => base * overlay,
+            BlendMode::Screen => base + overlay - base * overlay,
+            // Hard light with the colors swapped
+            BlendMode::Overlay => {
+                if base <= 0.5 {
+                    overlay * 2.0 * base
+                } else {
+                    BlendMode::Screen.blend(overlay, 2.0 * base - 1.0)
+                }
+            }
+            BlendMode::Darken => base.min(overlay),
+            BlendMode::Lighten => base.max(overlay),
+            BlendMode::SoftLight => {
+                if overlay <= 0.5 {
+                    base - (1.0 - 2.0 * overlay) * base * (1.0 - base)
+                } else {
+                    let d = if base <= 0.25 {
+                        ((16.0 * base - 12.0) * base + 4.0) * base
+                    } else {
+                        base.sqrt()
+                    };
+                    base + (2.0 * overlay - 1.0) * (d - base)
+                }
+            }
+            BlendMode::ColorDodge => {
+                if base <= 0.0 {
+                    0.0
+                } else if overlay >= 1.0 {
+                    1.0
+                } else {
+                    (base / (1.0 - overlay)).min(1.0)
+                }
+            }
+            BlendMode::Difference => (base - overlay).abs(),
+        }
+    }
+}
+
+/// A shadow drawn behind text, or a glow when it isn't offset.
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct TextShadow {
+    /// Horizontal offset of the shadow, in pixels.
+    pub offset_x: f32,
+    /// Vertical offset of the shadow, in pixels.
+    pub offset_y: f32,
+    /// Blur radius of the shadow, in pixels. The shadow has sharp edges when it's 0.
+    pub blur: f32,
+    /// The color of the shadow.
+    pub color: SiColor,
+}
+
+#[wasm_bindgen]
+impl TextShadow {
+    /// Creates a new TextShadow, like [`TextShadow::new`], with the color in CSS syntax.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SiError::Color`] if the color is not valid.
+    #[wasm_bindgen(constructor)]
+    pub fn js_new(
+        offset_x: f32,
+        offset_y: f32,
+        blur: f32,
+        color: &str,
+    ) -> Result<TextShadow, SiError> {
+        let color = SiColor::parse(color)?;
+        Ok(TextShadow::new(offset_x, offset_y, blur, color))
+    }
+}
+
+impl TextShadow {
+    /// Creates a new TextShadow.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset_x` - Horizontal offset of the shadow, in pixels.
+    /// * `offset_y` - Vertical offset of the shadow, in pixels.
+    /// * `blur` - Blur radius of the shadow, in pixels.
+    /// * `color` - The color of the shadow.
+    pub fn new(offset_x: f32, offset_y: f32, blur: f32, color: SiColor) -> TextShadow {
+        TextShadow {
+            offset_x,
+            offset_y,
+            blur,
+            color,
+        }
+    }
+}
+
+/// Options controlling how text is laid out and styled.
 #[wasm_bindgen]
 #[derive(Clone)]
 pub struct TextOptions {
+    /// Extra tracking added after every glyph, in pixels.
     pub letter_spacing: f32,
-    pub space_width: f32,
+    /// Fixed advance for whitespace, in pixels. Uses the font's own advance when unset.
+    pub space_width: Option<f32>,
+    /// Distance between baselines, as a multiple of the font's own line height.
+    pub line_height: f32,
+    /// Base direction of the text, used to order mixed-direction text.
+    pub direction: TextDirection,
+    /// Horizontal alignment of the text within its box, or around its position.
+    pub align: TextAlign,
+    /// Vertical alignment of the text within its box, or around its position.
+    pub vertical_align: VerticalAlign,
+    /// The maximum number of lines to render.
+    pub max_lines: Option<usize>,
+    /// What to do when there are more lines than `max_lines`, or than fit in the box.
+    pub overflow: TextOverflow,
+    /// The suffix used by [`TextOverflow::Ellipsis`].
+    #[wasm_bindgen(getter_with_clone)]
+    pub ellipsis: String,
+    /// Whether the glyphs are filled with the text color.
+    pub fill: bool,
+    /// Width of the stroke around the glyphs, in pixels. No stroke is drawn when it's 0.
+    pub stroke_width: f32,
+    /// The color of the stroke.
+    pub stroke_color: SiColor,
+    /// Where the stroke is drawn relative to the outline of the glyphs.
+    pub stroke_position: StrokePosition,
+    /// Shadows drawn behind the text, with the first one on top.
+    #[wasm_bindgen(getter_with_clone)]
+    pub shadows: Vec<TextShadow>,
+    /// Paint used to fill the glyphs instead of the text color, such as a gradient.
+    #[wasm_bindgen(getter_with_clone)]
+    pub paint: Option<SiPaint>,
+    /// How the fill of the glyphs is blended with the image. Strokes and shadows are always
+    /// drawn normally.
+    pub blend_mode: BlendMode,
+    /// The opacity of the text, from 0 (invisible) to 1 (opaque). The alpha of fills,
+    /// strokes, shadows and color glyphs is multiplied by it. Text with an opacity which
+    /// isn't a finite number is invisible.
+    pub opacity: f32,
 }
 
 #[wasm_bindgen]
 impl TextOptions {
-    #[wasm_bindgen]
-    pub fn default() -> Self {
+    /// Creates the default TextOptions.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates the default TextOptions.
+    #[wasm_bindgen(js_name = "default")]
+    pub fn js_default() -> Self {
+        Self::default()
+    }
+}
+
+impl Default for TextOptions {
+    fn default() -> Self {
         TextOptions {
-            letter_spacing: 2.0,
-            space_width: 10.0,
+            letter_spacing: 0.0,
+            space_width: None,
+            line_height: 1.0,
+            direction: TextDirection::Auto,
+            align: TextAlign::Start,
+            vertical_align: VerticalAlign::Top,
+            max_lines: None,
+            overflow: TextOverflow::Clip,
+            ellipsis: String::from("\u{2026}"),
+            fill: true,
+            stroke_width: 0.0,
+            stroke_color: SiColor::BLACK,
+            stroke_position: StrokePosition::Outside,
+            shadows: Vec::new(),
+            paint: None,
+            blend_mode: BlendMode::Normal,
+            opacity: 1.0,
+        }
+    }
+}
+
+/// Options controlling how an image is drawn onto another.
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct ImageOptions {
+    /// How the image is blended with the image under it.
+    pub blend_mode: BlendMode,
+    /// The opacity of the image, from 0 (invisible) to 1 (as it is). The alpha of every
+    /// pixel is multiplied by it. An image with an opacity which isn't a finite number is
+    /// invisible.
+    pub opacity: f32,
+}
+
+#[wasm_bindgen]
+impl ImageOptions {
+    /// Creates the default ImageOptions.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates the default ImageOptions.
+    #[wasm_bindgen(js_name = "default")]
+    pub fn js_default() -> Self {
+        Self::default()
+    }
+}
+
+impl Default for ImageOptions {
+    fn default() -> Self {
+        ImageOptions {
+            blend_mode: BlendMode::Normal,
+            opacity: 1.0,
         }
     }
 }
@@ -33,6 +335,8 @@ pub struct SiImage {
     image: DynamicImage,
     height: u32,
     width: u32,
+    /// Whether blending and resampling happen in linear light.
+    gamma_correct: bool,
 }
 
 #[wasm_bindgen]
@@ -43,20 +347,25 @@ impl SiImage {
     ///
     /// * `src` - The vector of image data.
     #[wasm_bindgen(constructor)]
-    pub fn new(src: Vec<u8>) -> Self {
+    pub fn new(src: Vec<u8>) -> Result<SiImage, SiError> {
         Self::from_vec(src)
     }
 
     /// Creates a new SiImage from a vector of image data.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SiError::Decode`] if the data is not a supported image.
     #[wasm_bindgen]
-    pub fn from_vec(vec: Vec<u8>) -> SiImage {
-        let image = image::load_from_memory(&vec).expect("Could not decode image");
+    pub fn from_vec(vec: Vec<u8>) -> Result<SiImage, SiError> {
+        let image = image::load_from_memory(&vec).map_err(|e| SiError::Decode(e.to_string()))?;
         let (width, height) = image.dimensions();
-        SiImage {
+        Ok(SiImage {
             image,
             height,
             width,
-        }
+            gamma_correct: false,
+        })
     }
 
     /// Creates a new SiImage from image data fetched from a network URL asynchronously.
@@ -64,29 +373,31 @@ impl SiImage {
     /// # Arguments
     ///
     /// * `image_url` - The URL from which to fetch the image data.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SiError::Fetch`] if the request fails, or [`SiError::Decode`]
+    /// if the response is not a supported image.
     #[wasm_bindgen]
     #[cfg(feature = "async")]
-    pub async fn from_network_async(image_url: &str) -> SiImage {
+    pub async fn from_network_async(image_url: &str) -> Result<SiImage, SiError> {
         let image_data: Vec<u8> = reqwest::get(image_url)
-            .await
-            .expect("Could not fetch image")
+            .await?
+            .error_for_status()?
             .bytes()
-            .await
-            .expect("Could not extract image")
+            .await?
             .into();
-        let image = image::load_from_memory(&image_data).expect("Could not decode image");
-        let (width, height) = image.dimensions();
-        Self {
-            image,
-            height,
-            width,
-        }
+        Self::from_vec(image_data)
     }
 
     /// Placeholder method for when async feature is not enabled.
+    ///
+    /// # Errors
+    ///
+    /// Always returns [`SiError::Fetch`], since fetching requires the `async` feature.
     #[cfg(not(feature = "async"))]
-    pub fn from_network_async(_image_url: &str) {
-        panic!("async feature not enabled")
+    pub fn from_network_async(_image_url: &str) -> Result<SiImage, SiError> {
+        Err(SiError::Fetch(String::from("async feature not enabled")))
     }
 
     /// Creates a new SiImage from image data fetched from a network URL synchronously.
@@ -94,31 +405,33 @@ impl SiImage {
     /// # Arguments
     ///
     /// * `image_url` - The URL from which to fetch the image data.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SiError::Fetch`] if the request fails, or [`SiError::Decode`]
+    /// if the response is not a supported image.
     #[cfg(feature = "blocking")]
-    pub fn from_network(image_url: &str) -> SiImage {
-        // Load image data from either URL or provided bytes.
-        let image_data: Vec<u8> = reqwest::blocking::get(image_url)
-            .expect("Could not fetch image")
-            .bytes()
-            .expect("Could not extract image")
+    pub fn from_network(image_url: &str) -> Result<SiImage, SiError> {
+        let image_data: Vec<u8> = reqwest::blocking::get(image_url)?
+            .error_for_status()?
+            .bytes()?
             .into();
-
-        let image = image::load_from_memory(&image_data).expect("Could not decode image");
-        let (width, height) = image.dimensions();
-
-        Self {
-            image,
-            height,
-            width,
-        }
+        Self::from_vec(image_data)
     }
 
     /// Placeholder method for when blocking feature is not enabled.
+    ///
+    /// # Errors
+    ///
+    /// Always returns [`SiError::Fetch`], since fetching requires the `blocking` feature.
     #[cfg(not(feature = "blocking"))]
-    pub fn from_network(image_url: &str) {
-        panic!("blocking feature not enabled")
+    pub fn from_network(_image_url: &str) -> Result<SiImage, SiError> {
+        Err(SiError::Fetch(String::from("blocking feature not enabled")))
     }
+}
 
+// Text colors are `SiColor`s in Rust, but CSS strings in JavaScript
+impl SiImage {
     /// Renders text onto the image.
     ///
     /// # Arguments
@@ -127,14 +440,159 @@ impl SiImage {
     /// * `text_scale` - The scale of the rendered text.
     /// * `pos_x` - The X-coordinate position for rendering.
     /// * `pos_y` - The Y-coordinate position for rendering.
-    /// * `color` - The color of the rendered text in hexadecimal format (e.g., "#RRGGBB").
+    /// * `color` - The color of the rendered text, or `None` for black.
     /// * `using_font` - The SiFont used for text rendering on the image.
+    /// * `options` - The TextOptions used for laying out the text.
     ///
     /// # Returns
     ///
     /// A mutable instance of the main image, with the text rendered on it.
-    #[wasm_bindgen(js_name = "text")]
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SiError::Overflow`] if the text doesn't fit and the overflow policy is
+    /// [`TextOverflow::Error`].
+    #[allow(clippy::too_many_arguments)]
     pub fn render_text(
+        self,
+        text: &str,
+        text_scale: f32,
+        pos_x: f32,
+        pos_y: f32,
+        color: Option<SiColor>,
+        using_font: &SiFont,
+        options: &TextOptions,
+    ) -> Result<SiImage, SiError> {
+        let layout = using_font.layout(
+            text,
+            text_scale,
+            color.unwrap_or(SiColor::BLACK),
+            None,
+            None,
+            options,
+        )?;
+        Ok(self.draw_text(&layout, (pos_x, pos_y), options))
+    }
+
+    /// Renders text onto the image, wrapped to fit inside a box.
+    ///
+    /// Lines are broken at explicit line breaks (`\n`) and wrapped at Unicode line break
+    /// opportunities so that no line is wider than `max_width`.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to render on the image.
+    /// * `text_scale` - The scale of the rendered text.
+    /// * `pos_x` - The X-coordinate of the top-left corner of the box.
+    /// * `pos_y` - The Y-coordinate of the top-left corner of the box.
+    /// * `max_width` - The width of the box.
+    /// * `max_height` - The height of the box, if any. Lines which don't fit are not rendered.
+    /// * `color` - The color of the rendered text, or `None` for black.
+    /// * `using_font` - The SiFont used for text rendering on the image.
+    /// * `options` - The TextOptions used for laying out the text.
+    ///
+    /// # Returns
+    ///
+    /// A mutable instance of the main image, with the text rendered on it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SiError::Overflow`] if the text doesn't fit and the overflow policy is
+    /// [`TextOverflow::Error`], or [`SiError::InvalidArgument`] if the size of the box is
+    /// negative or not finite.
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_text_box(
+        self,
+        text: &str,
+        text_scale: f32,
+        pos_x: f32,
+        pos_y: f32,
+        max_width: f32,
+        max_height: Option<f32>,
+        color: Option<SiColor>,
+        using_font: &SiFont,
+        options: &TextOptions,
+    ) -> Result<SiImage, SiError> {
+        let layout = using_font.layout(
+            text,
+            text_scale,
+            color.unwrap_or(SiColor::BLACK),
+            Some(max_width),
+            max_height,
+            options,
+        )?;
+        Ok(self.draw_text(&layout, (pos_x, pos_y), options))
+    }
+
+    /// Renders text onto the image, at the largest scale that fits inside a box.
+    ///
+    /// The scale is chosen with [`SiFont::fit_scale`], which can also be used to find out
+    /// which scale the text was rendered at.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to render on the image.
+    /// * `min_scale` - The smallest scale the text may be rendered at.
+    /// * `max_scale` - The largest scale the text may be rendered at.
+    /// * `pos_x` - The X-coordinate of the top-left corner of the box.
+    /// * `pos_y` - The Y-coordinate of the top-left corner of the box.
+    /// * `max_width` - The width of the box.
+    /// * `max_height` - The height of the box.
+    /// * `color` - The color of the rendered text, or `None` for black.
+    /// * `using_font` - The SiFont used for text rendering on the image.
+    /// * `options` - The TextOptions used for laying out the text.
+    ///
+    /// # Returns
+    ///
+    /// A mutable instance of the main image, with the text rendered on it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SiError::Overflow`] if the text doesn't fit and the overflow policy is
+    /// [`TextOverflow::Error`], or [`SiError::InvalidArgument`] if the size of the box is
+    /// negative or not finite.
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_text_fit(
+        self,
+        text: &str,
+        min_scale: f32,
+        max_scale: f32,
+        pos_x: f32,
+        pos_y: f32,
+        max_width: f32,
+        max_height: f32,
+        color: Option<SiColor>,
+        using_font: &SiFont,
+        options: &TextOptions,
+    ) -> Result<SiImage, SiError> {
+        let text_scale =
+            using_font.fit_scale(text, min_scale, max_scale, max_width, max_height, options);
+        self.render_text_box(
+            text,
+            text_scale,
+            pos_x,
+            pos_y,
+            max_width,
+            Some(max_height),
+            color,
+            using_font,
+            options,
+        )
+    }
+}
+
+#[wasm_bindgen]
+impl SiImage {
+    /// Renders text onto the image, like [`SiImage::render_text`], with the color in CSS
+    /// syntax.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SiError::Color`] if the color is not valid, or any error
+    /// [`SiImage::render_text`] returns.
+    #[wasm_bindgen(js_name = "text")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn js_render_text(
         self,
         text: &str,
         text_scale: f32,
@@ -143,34 +601,130 @@ impl SiImage {
         color: Option<String>,
         using_font: &SiFont,
         options: &TextOptions,
-    ) -> SiImage {
-        let mut image = self.image.clone(); // Clone the image explicitly for the method
-        let font = &using_font.font.as_scaled(text_scale);
-        let ascent = font.ascent();
-
-        let parsed_color = match color.as_ref() {
-            Some(c) => hex_to_rgb(c).unwrap_or_else(|| {
-                // Log an error, if necessary
-                eprintln!("Invalid color hex: {}", c);
-                Rgb([0, 0, 0])
-            }),
-            None => Rgb([0, 0, 0]),
-        };
+    ) -> Result<SiImage, SiError> {
+        let color = parse_optional(color)?;
+        self.render_text(text, text_scale, pos_x, pos_y, color, using_font, options)
+    }
 
-        for glyph in &using_font.layout(text, text_scale, (pos_x, pos_y + ascent), options) {
-            let bb = glyph.px_bounds();
-            glyph.draw(|_x, _y, v| {
-                let x = _x + bb.min.x as u32;
-                let y = _y + bb.min.y as u32;
-                if x < image.width() && y < image.height() {
-                    let pixel = image.get_pixel(x, y);
-                    let new_pixel = blend_pixel(&pixel, parsed_color, v);
-                    image.put_pixel(x, y, new_pixel);
-                }
-            });
-        }
+    /// Renders text onto the image, wrapped to fit inside a box, like
+    /// [`SiImage::render_text_box`], with the color in CSS syntax.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SiError::Color`] if the color is not valid, or any error
+    /// [`SiImage::render_text_box`] returns.
+    #[wasm_bindgen(js_name = "textBox")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn js_render_text_box(
+        self,
+        text: &str,
+        text_scale: f32,
+        pos_x: f32,
+        pos_y: f32,
+        max_width: f32,
+        max_height: Option<f32>,
+        color: Option<String>,
+        using_font: &SiFont,
+        options: &TextOptions,
+    ) -> Result<SiImage, SiError> {
+        let color = parse_optional(color)?;
+        self.render_text_box(
+            text, text_scale, pos_x, pos_y, max_width, max_height, color, using_font, options,
+        )
+    }
+
+    /// Renders text onto the image, at the largest scale that fits inside a box, like
+    /// [`SiImage::render_text_fit`], with the color in CSS syntax.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SiError::Color`] if the color is not valid, or any error
+    /// [`SiImage::render_text_fit`] returns.
+    #[wasm_bindgen(js_name = "textFit")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn js_render_text_fit(
+        self,
+        text: &str,
+        min_scale: f32,
+        max_scale: f32,
+        pos_x: f32,
+        pos_y: f32,
+        max_width: f32,
+        max_height: f32,
+        color: Option<String>,
+        using_font: &SiFont,
+        options: &TextOptions,
+    ) -> Result<SiImage, SiError> {
+        let color = parse_optional(color)?;
+        self.render_text_fit(
+            text, min_scale, max_scale, pos_x, pos_y, max_width, max_height, color, using_font,
+            options,
+        )
+    }
+
+    /// Renders rich text onto the image.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The SiRichText to render on the image.
+    /// * `pos_x` - The X-coordinate position for rendering.
+    /// * `pos_y` - The Y-coordinate position for rendering.
+    /// * `options` - The TextOptions used for laying out the text.
+    ///
+    /// # Returns
+    ///
+    /// A mutable instance of the main image, with the text rendered on it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SiError::Overflow`] if the text doesn't fit and the overflow policy is
+    /// [`TextOverflow::Error`].
+    #[wasm_bindgen(js_name = "richText")]
+    pub fn render_rich_text(
+        self,
+        text: &SiRichText,
+        pos_x: f32,
+        pos_y: f32,
+        options: &TextOptions,
+    ) -> Result<SiImage, SiError> {
+        let layout = text.layout(None, None, options)?;
+        Ok(self.draw_text(&layout, (pos_x, pos_y), options))
+    }
 
-        SiImage { image, height: self.height, width: self.width }
+    /// Renders rich text onto the image, wrapped to fit inside a box.
+    ///
+    /// The spans are wrapped together, as in [`SiImage::render_text_box`].
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The SiRichText to render on the image.
+    /// * `pos_x` - The X-coordinate of the top-left corner of the box.
+    /// * `pos_y` - The Y-coordinate of the top-left corner of the box.
+    /// * `max_width` - The width of the box.
+    /// * `max_height` - The height of the box, if any. Lines which don't fit are not rendered.
+    /// * `options` - The TextOptions used for laying out the text.
+    ///
+    /// # Returns
+    ///
+    /// A mutable instance of the main image, with the text rendered on it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SiError::Overflow`] if the text doesn't fit and the overflow policy is
+    /// [`TextOverflow::Error`], or [`SiError::InvalidArgument`] if the size of the box is
+    /// negative or not finite.
+    #[wasm_bindgen(js_name = "richTextBox")]
+    pub fn render_rich_text_box(
+        self,
+        text: &SiRichText,
+        pos_x: f32,
+        pos_y: f32,
+        max_width: f32,
+        max_height: Option<f32>,
+        options: &TextOptions,
+    ) -> Result<SiImage, SiError> {
+        let layout = text.layout(Some(max_width), max_height, options)?;
+        Ok(self.draw_text(&layout, (pos_x, pos_y), options))
     }
 
     /// Renders some image into the image
@@ -185,8 +739,37 @@ impl SiImage {
     ///
     /// A mutable instance of the main image, with overlay of the provided one
     #[wasm_bindgen(js_name = "image")]
-    pub fn render_image(mut self, image: &SiImage, pos_x: i64, pos_y: i64) -> SiImage {
-        overlay(&mut self.image, &image.image, pos_x, pos_y);
+    pub fn render_image(self, image: &SiImage, pos_x: i64, pos_y: i64) -> SiImage {
+        self.render_image_with_options(image, pos_x, pos_y, &ImageOptions::default())
+    }
+
+    /// Renders some image into the image, blended as set in the options
+    ///
+    /// # Arguments
+    ///
+    /// * `image` - The SiImage to render.
+    /// * `pos_x` - The X-coordinate position for rendering.
+    /// * `pos_y` - The Y-coordinate position for rendering.
+    /// * `options` - The ImageOptions used for drawing the image.
+    ///
+    /// # Returns
+    ///
+    /// A mutable instance of the main image, with overlay of the provided one
+    #[wasm_bindgen(js_name = "imageWithOptions")]
+    pub fn render_image_with_options(
+        mut self,
+        image: &SiImage,
+        pos_x: i64,
+        pos_y: i64,
+        options: &ImageOptions,
+    ) -> SiImage {
+        self.composite(
+            &image.rgba_image(),
+            pos_x,
+            pos_y,
+            options.blend_mode,
+            options.opacity,
+        );
         self
     }
 
@@ -195,14 +778,17 @@ impl SiImage {
     /// # Returns
     ///
     /// The image data as bytes in PNG format
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SiError::Encode`] if the image could not be encoded.
     #[wasm_bindgen]
-    pub fn to_bytes(&self) -> Vec<u8> {
+    pub fn to_bytes(&self) -> Result<Vec<u8>, SiError> {
         let mut v = std::io::Cursor::new(Vec::new());
-        self.clone()
-            .image
+        self.image
             .write_to(&mut v, image::ImageFormat::Png)
-            .expect("Could not write bytes");
-        v.into_inner()
+            .map_err(|e| SiError::Encode(e.to_string()))?;
+        Ok(v.into_inner())
     }
 
     /// Gets the height of the image.
@@ -237,45 +823,470 @@ impl SiImage {
     /// A mutable instance of the main image, with the resized image
     #[wasm_bindgen]
     pub fn resize(mut self, width: u32, height: u32) -> SiImage {
-        let new_image = DynamicImage::ImageRgba8(resize(
-            &self.image,
+        let new_image = DynamicImage::ImageRgba8(resample(
+            &self.rgba_image(),
             width,
             height,
-            image::imageops::FilterType::Triangle,
+            self.gamma_correct,
         ));
         let _ = std::mem::replace(&mut self.image, new_image);
         let _ = std::mem::replace(&mut self.width, width);
         let _ = std::mem::replace(&mut self.height, height);
         self
     }
+
+    /// Sets whether blending and resampling happen in linear light.
+    ///
+    /// Pixels are stored sRGB-encoded, and blending those values directly makes the
+    /// anti-aliased edges of light text on a dark background look thin, and of dark text on
+    /// a light background look bold. With gamma correction, colors are converted to linear
+    /// light before text, shadows and images are blended in and images are resized, and
+    /// converted back afterwards, so text weight looks the same on both.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether to blend and resample in linear light. Off by default.
+    ///
+    /// # Returns
+    ///
+    /// A mutable instance of the main image, which blends with the new setting
+    #[wasm_bindgen(js_name = "withGammaCorrection")]
+    pub fn with_gamma_correction(mut self, enabled: bool) -> SiImage {
+        self.gamma_correct = enabled;
+        self
+    }
 }
 
 impl SiImage {
+    /// Creates a new SiImage from an image file.
+    /// **NOTE**: It doesn't work in WASM. Only for direct usage.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the image file.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SiError::Io`] if the file can't be read, or [`SiError::Decode`]
+    /// if it is not a supported image.
+    pub fn from_path(path: impl AsRef<std::path::Path>) -> Result<SiImage, SiError> {
+        Self::from_vec(std::fs::read(path)?)
+    }
+
+    /// Draws laid out text, with the top-left corner of its box at `position`.
+    fn draw_text(
+        mut self,
+        layout: &TextLayout,
+        position: Position,
+        options: &TextOptions,
+    ) -> SiImage {
+        let colors: Vec<SiPaint> = layout
+            .styles
+            .iter()
+            .map(|style| SiPaint::solid(style.color))
+            .collect();
+        let fills: Vec<&SiPaint> = colors
+            .iter()
+            .map(|color| options.paint.as_ref().unwrap_or(color))
+            .collect();
+
+        // Faces are only needed for color glyphs from COLR tables
+        let faces: Vec<Vec<Option<ttf_parser::Face>>> = layout
+            .styles
+            .iter()
+            .map(|style| style.font.fonts.iter().map(|font| font.face()).collect())
+            .collect();
+        let mut outlined_glyphs = Vec::new();
+        for line in &layout.lines {
+            for laid_out in &line.glyphs {
+                let mut glyph = laid_out.glyph.clone();
+                glyph.position.x += position.0 + line.x;
+                glyph.position.y += position.1 + line.baseline;
+                let stack = &layout.styles[laid_out.style].font;
+                let foreground = Rgba::from(layout.styles[laid_out.style].color).0;
+                let font = &stack.fonts[laid_out.font];
+
+                if let Some(path) = &laid_out.emoji {
+                    if let Ok(emoji) = image::open(path) {
+                        let size = glyph.scale.y;
+                        let top = glyph.position.y - stack.primary().as_scaled(size).ascent();
+                        self.draw_glyph_image(
+                            &emoji.to_rgba8(),
+                            glyph.position.x,
+                            top,
+                            size,
+                            size,
+                            options.opacity,
+                        );
+                    }
+                    continue;
+                }
+
+                if let Some(layers) = faces[laid_out.style][laid_out.font]
+                    .as_ref()
+                    .and_then(|face| emoji::color_layers(face, glyph.id, foreground))
+                {
+                    for layer in layers {
+                        let mut layer_glyph = glyph.clone();
+                        layer_glyph.id = layer.glyph;
+                        if let Some(outlined) = font.outline_glyph(layer_glyph) {
+                            self.fill_glyph(&outlined, Rgba(layer.color), options.opacity);
+                        }
+                    }
+                    continue;
+                }
+
+                let scaled = font.as_scaled(glyph.scale);
+                let pixels_per_em = font.units_per_em().unwrap_or(1000.0) * scaled.h_scale_factor();
+                if let Some(raster) = font.glyph_raster_image2(glyph.id, pixels_per_em as u16) {
+                    if let (GlyphImageFormat::Png, Ok(bitmap)) =
+                        (&raster.format, image::load_from_memory(raster.data))
+                    {
+                        let factor = pixels_per_em / raster.pixels_per_em as f32;
+                        self.draw_glyph_image(
+                            &bitmap.to_rgba8(),
+                            glyph.position.x + raster.origin.x * factor,
+                            glyph.position.y - scaled.ascent() + raster.origin.y * factor,
+                            bitmap.width() as f32 * factor,
+                            bitmap.height() as f32 * factor,
+                            options.opacity,
+                        );
+                        continue;
+                    }
+                }
+
+                if let Some(outlined) = font.outline_glyph(glyph) {
+                    outlined_glyphs.push((laid_out.style, outlined));
+                }
+            }
+        }
+        self.draw_outlines(&outlined_glyphs, &fills, position, options);
+        self
+    }
+
+    /// Fills and strokes the outlines of glyphs, as a single shape.
+    ///
+    /// Each glyph is paired with the index of its style, which picks its fill from `fills`.
+    fn draw_outlines(
+        &mut self,
+        glyphs: &[(usize, OutlinedGlyph)],
+        fills: &[&SiPaint],
+        origin: Position,
+        options: &TextOptions,
+    ) {
+        let stroke_width = options.stroke_width.max(0.0);
+        if glyphs.is_empty() || (!options.fill && stroke_width == 0.0) {
+            return;
+        }
+
+        // The mask covers the glyphs, plus room for the stroke, but not more than the image
+        // and the shadows which may fall onto it
+        let padding = stroke_width.ceil() as i64 + 1;
+        let margin = padding
+            + options
+                .shadows
+                .iter()
+                .map(|shadow| {
+                    (shadow.offset_x.abs().max(shadow.offset_y.abs()) + shadow.blur.max(0.0) * 1.5)
+                        .ceil() as i64
+                })
+                .max()
+                .unwrap_or(0);
+        let bounds = glyphs
+            .iter()
+            .map(|(_, glyph)| glyph.px_bounds())
+            .reduce(|a, b| ab_glyph::Rect {
+                min: ab_glyph::point(a.min.x.min(b.min.x), a.min.y.min(b.min.y)),
+                max: ab_glyph::point(a.max.x.max(b.max.x), a.max.y.max(b.max.y)),
+            })
+            .unwrap_or_default();
+        let min_x = (bounds.min.x.floor() as i64 - padding).max(-margin);
+        let min_y = (bounds.min.y.floor() as i64 - padding).max(-margin);
+        let max_x = (bounds.max.x.ceil() as i64 + padding).min(self.image.width() as i64 + margin);
+        let max_y = (bounds.max.y.ceil() as i64 + padding).min(self.image.height() as i64 + margin);
+        if min_x >= max_x || min_y >= max_y {
+            return;
+        }
+        let region = Mask::new(
+            min_x,
+            min_y,
+            (max_x - min_x) as usize,
+            (max_y - min_y) as usize,
+        );
+        let mut fill_masks = vec![region; fills.len()];
+        for (style, glyph) in glyphs {
+            fill_masks[*style].add_glyph(glyph);
+        }
+        // Strokes and shadows are drawn around all of the glyphs at once
+        let mask = fill_masks[1..]
+            .iter()
+            .fold(fill_masks[0].clone(), |mask, other| mask.union(other));
+
+        let stroke = (stroke_width > 0.0).then(|| {
+            let stroke_color = SiPaint::solid(options.stroke_color);
+            let ring = match options.stroke_position {
+                StrokePosition::Outside => mask.dilate(stroke_width).subtract(&mask),
+                StrokePosition::Inside => mask.subtract(&mask.erode(stroke_width)),
+                StrokePosition::Center => mask
+                    .dilate(stroke_width / 2.0)
+                    .subtract(&mask.erode(stroke_width / 2.0)),
+            };
+            (ring, stroke_color)
+        });
+
+        if !options.shadows.is_empty() {
+            let shape = match &stroke {
+                Some((ring, _)) if options.fill => mask.union(ring),
+                Some((ring, _)) => ring.clone(),
+                None => mask.clone(),
+            };
+            for shadow in options.shadows.iter().rev() {
+                let shadow_color = SiPaint::solid(shadow.color);
+                let shadow_mask = shape
+                    .blur(shadow.blur)
+                    .offset(shadow.offset_x, shadow.offset_y);
+                self.fill_mask(
+                    &shadow_mask,
+                    &shadow_color,
+                    origin,
+                    options.opacity,
+                    BlendMode::Normal,
+                );
+            }
+        }
+
+        // Outside strokes go under the fill, so that the two blend along the outline
+        match (&stroke, options.stroke_position) {
+            (Some((ring, stroke_color)), StrokePosition::Outside) => {
+                self.fill_mask(
+                    ring,
+                    stroke_color,
+                    origin,
+                    options.opacity,
+                    BlendMode::Normal,
+                );
+                if options.fill {
+                    self.fill_styles(&fill_masks, fills, origin, options);
+                }
+            }
+            _ => {
+                if options.fill {
+                    self.fill_styles(&fill_masks, fills, origin, options);
+                }
+                if let Some((ring, stroke_color)) = &stroke {
+                    self.fill_mask(
+                        ring,
+                        stroke_color,
+                        origin,
+                        options.opacity,
+                        BlendMode::Normal,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Fills the outline of a glyph with a color.
+    fn fill_glyph(&mut self, glyph: &OutlinedGlyph, color: Rgba<u8>, opacity: f32) {
+        let bb = glyph.px_bounds();
+        let gamma_correct = self.gamma_correct;
+        glyph.draw(|_x, _y, v| {
+            let x = _x as i64 + bb.min.x as i64;
+            let y = _y as i64 + bb.min.y as i64;
+            if x >= 0 && y >= 0 && x < self.image.width() as i64 && y < self.image.height() as i64 {
+                let pixel = self.image.get_pixel(x as u32, y as u32);
+                let new_pixel =
+                    blend_pixel(&pixel, color, v * opacity, BlendMode::Normal, gamma_correct);
+                self.image.put_pixel(x as u32, y as u32, new_pixel);
+            }
+        });
+    }
+
+    /// Fills the mask of each style with the fill of that style.
+    fn fill_styles(
+        &mut self,
+        masks: &[Mask],
+        fills: &[&SiPaint],
+        origin: Position,
+        options: &TextOptions,
+    ) {
+        for (mask, fill) in masks.iter().zip(fills) {
+            self.fill_mask(mask, fill, origin, options.opacity, options.blend_mode);
+        }
+    }
+
+    /// Fills the covered pixels of a mask with a paint, sampled at the center of each pixel.
+    fn fill_mask(
+        &mut self,
+        mask: &Mask,
+        paint: &SiPaint,
+        origin: Position,
+        opacity: f32,
+        mode: BlendMode,
+    ) {
+        for (x, y, v) in mask.pixels() {
+            if x >= 0 && y >= 0 && x < self.image.width() as i64 && y < self.image.height() as i64 {
+                let color = paint.color_at(x as f32 + 0.5 - origin.0, y as f32 + 0.5 - origin.1);
+                let pixel = self.image.get_pixel(x as u32, y as u32);
+                let new_pixel = blend_pixel(&pixel, color, v * opacity, mode, self.gamma_correct);
+                self.image.put_pixel(x as u32, y as u32, new_pixel);
+            }
+        }
+    }
+
+    /// Gets a copy of the image as RGBA pixels.
+    pub(crate) fn rgba_image(&self) -> RgbaImage {
+        self.image.to_rgba8()
+    }
+
+    /// Draws the image of a glyph (such as a color emoji), scaled to the given size.
+    fn draw_glyph_image(
+        &mut self,
+        glyph: &RgbaImage,
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        opacity: f32,
+    ) {
+        let (width, height) = (width.round() as u32, height.round() as u32);
+        if width == 0 || height == 0 {
+            return;
+        }
+        let scaled = resample(glyph, width, height, self.gamma_correct);
+        self.composite(
+            &scaled,
+            x.round() as i64,
+            y.round() as i64,
+            BlendMode::Normal,
+            opacity,
+        );
+    }
+
+    /// Composites an image over this one, with its top-left corner at a position.
+    fn composite(
+        &mut self,
+        image: &RgbaImage,
+        pos_x: i64,
+        pos_y: i64,
+        mode: BlendMode,
+        opacity: f32,
+    ) {
+        for (x, y, color) in image.enumerate_pixels() {
+            let (x, y) = (pos_x + x as i64, pos_y + y as i64);
+            if x >= 0 && y >= 0 && x < self.image.width() as i64 && y < self.image.height() as i64 {
+                let pixel = self.image.get_pixel(x as u32, y as u32);
+                let new_pixel = blend_pixel(&pixel, *color, opacity, mode, self.gamma_correct);
+                self.image.put_pixel(x as u32, y as u32, new_pixel);
+            }
+        }
+    }
+
     /// Load a preset.
     /// **NOTE**: It doesn't work in WASM. Only for direct usage.
+    ///
+    /// # Errors
+    ///
+    /// Returns whatever error the preset callback raised, typically
+    /// [`SiError::PresetArgument`] for missing or mistyped values.
     pub fn load_preset(
         &mut self,
-        preset: Box<SiPreset>,
-        values: std::collections::HashMap<String, Box<dyn std::any::Any>>,
-    ) -> &mut SiImage {
-        let res = (preset.cb)(self, values);
+        preset: &SiPreset,
+        values: PresetValues,
+    ) -> Result<&mut SiImage, SiError> {
+        let res = (preset.cb)(self, values)?;
         let _ = std::mem::replace(self, res);
-        self
+        Ok(self)
     }
 }
 
-/// Helper function to blend colors
-fn blend_pixel(base_pixel: &Rgba<u8>, color: Rgb<u8>, alpha: f32) -> Rgba<u8> {
+/// Composites a color over a pixel with Porter-Duff "source-over", so that both the alpha
+/// of the color (scaled by `alpha`, e.g. the coverage of a glyph) and the alpha of the pixel
+/// are respected. The color is first blended with the pixel using `mode`, where the pixel is
+/// opaque. With `gamma_correct`, the color channels are blended in linear light.
+///
+/// An `alpha` which isn't a finite number leaves the pixel as it is.
+fn blend_pixel(
+    base_pixel: &Rgba<u8>,
+    color: Rgba<u8>,
+    alpha: f32,
+    mode: BlendMode,
+    gamma_correct: bool,
+) -> Rgba<u8> {
+    if !alpha.is_finite() {
+        return *base_pixel;
+    }
+    let src_alpha = color[3] as f32 / 255.0 * alpha.clamp(0.0, 1.0);
+    let dst_alpha = base_pixel[3] as f32 / 255.0;
+    let out_alpha = src_alpha + dst_alpha * (1.0 - src_alpha);
+    if out_alpha <= 0.0 {
+        return Rgba([0, 0, 0, 0]);
+    }
+    let channel = |i: usize| {
+        let base = decode_channel(base_pixel[i], gamma_correct);
+        let overlay = decode_channel(color[i], gamma_correct);
+        // Where the pixel is transparent, there is nothing to blend with
+        let overlay = (1.0 - dst_alpha) * overlay + dst_alpha * mode.blend(base, overlay);
+        let blended = blend_channel(base, overlay, src_alpha, dst_alpha) / out_alpha;
+        encode_channel(blended, gamma_correct)
+    };
     Rgba([
-        blend_channel(base_pixel[0], color[0], alpha),
-        blend_channel(base_pixel[1], color[1], alpha),
-        blend_channel(base_pixel[2], color[2], alpha),
-        255,
+        channel(0),
+        channel(1),
+        channel(2),
+        (out_alpha * 255.0).round() as u8,
     ])
 }
 
-fn blend_channel(base: u8, overlay: u8, alpha: f32) -> u8 {
-    ((overlay as f32 * alpha) + (base as f32 * (1.0 - alpha))) as u8
+/// Blends a channel of a color over a channel of a pixel, weighted by their alphas. The
+/// result still has to be divided by the output alpha.
+fn blend_channel(base: f32, overlay: f32, src_alpha: f32, dst_alpha: f32) -> f32 {
+    overlay * src_alpha + base * dst_alpha * (1.0 - src_alpha)
+}
+
+/// Converts a color channel to a value from 0 to 1, in linear light with `gamma_correct`.
+fn decode_channel(channel: u8, gamma_correct: bool) -> f32 {
+    if gamma_correct {
+        srgb_to_linear(channel)
+    } else {
+        channel as f32 / 255.0
+    }
+}
+
+/// Converts a value from 0 to 1 back to a color channel, the inverse of [`decode_channel`].
+fn encode_channel(value: f32, gamma_correct: bool) -> u8 {
+    if gamma_correct {
+        linear_to_srgb(value)
+    } else {
+        (value.clamp(0.0, 1.0) * 255.0).round() as u8
+    }
+}
+
+/// Resizes an image, interpolating between pixels in linear light with `gamma_correct`.
+fn resample(image: &RgbaImage, width: u32, height: u32, gamma_correct: bool) -> RgbaImage {
+    let filter = image::imageops::FilterType::Triangle;
+    if !gamma_correct {
+        return resize(image, width, height, filter);
+    }
+    let linear = Rgba32FImage::from_fn(image.width(), image.height(), |x, y| {
+        let Rgba([r, g, b, a]) = *image.get_pixel(x, y);
+        Rgba([
+            srgb_to_linear(r),
+            srgb_to_linear(g),
+            srgb_to_linear(b),
+            a as f32 / 255.0,
+        ])
+    });
+    let resized = resize(&linear, width, height, filter);
+    RgbaImage::from_fn(width, height, |x, y| {
+        let Rgba([r, g, b, a]) = *resized.get_pixel(x, y);
+        Rgba([
+            linear_to_srgb(r),
+            linear_to_srgb(g),
+            linear_to_srgb(b),
+            (a.clamp(0.0, 1.0) * 255.0).round() as u8,
+        ])
+    })
 }
 
 /// Converts a hexadecimal color code to an RGB color.
@@ -287,6 +1298,7 @@ fn blend_channel(base: u8, overlay: u8, alpha: f32) -> u8 {
 /// # Returns
 ///
 /// An `Option` containing the RGB color as `Rgb<u8>`, or `None` if the conversion fails.
+#[deprecated(note = "use `SiColor::parse`, which supports alpha and fails on invalid colors")]
 pub fn hex_to_rgb(hex: &str) -> Option<Rgb<u8>> {
     let hex = hex.trim_start_matches('#'); // Remove "#" if present
     if hex.len() == 6 {
@@ -303,3 +1315,125 @@ pub fn hex_to_rgb(hex: &str) -> Option<Rgb<u8>> {
         Some(Rgb([255, 255, 255]))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filled(width: u32, height: u32, pixel: Rgba<u8>) -> SiImage {
+        SiImage {
+            image: DynamicImage::ImageRgba8(RgbaImage::from_pixel(width, height, pixel)),
+            height,
+            width,
+            gamma_correct: false,
+        }
+    }
+
+    fn assert_blends(mode: BlendMode, cases: &[(f32, f32, f32)]) {
+        for (base, overlay, expected) in cases {
+            let blended = mode.blend(*base, *overlay);
+            assert!(
+                (blended - expected).abs() < 1e-6,
+                "{:?}({}, {}) = {}, expected {}",
+                mode,
+                base,
+                overlay,
+                blended,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn blend_modes_follow_the_spec() {
+        assert_blends(
+            BlendMode::Multiply,
+            &[
+                (0.0, 1.0, 0.0),
+                (0.25, 0.5, 0.125),
+                (0.5, 0.5, 0.25),
+                (1.0, 0.25, 0.25),
+            ],
+        );
+        assert_blends(
+            BlendMode::Screen,
+            &[
+                (0.0, 1.0, 1.0),
+                (0.25, 0.5, 0.625),
+                (0.5, 0.5, 0.75),
+                (1.0, 0.25, 1.0),
+            ],
+        );
+        assert_blends(
+            BlendMode::SoftLight,
+            &[
+                (0.0, 0.75, 0.0),
+                (0.25, 0.0, 0.0625),
+                (0.25, 0.75, 0.375),
+                (0.25, 1.0, 0.5),
+                (0.5, 0.25, 0.375),
+                (0.5, 0.5, 0.5),
+                (1.0, 1.0, 1.0),
+            ],
+        );
+    }
+
+    #[test]
+    fn source_over_keeps_the_alpha_of_both_colors() {
+        let color = Rgba([255, 0, 0, 128]);
+        let transparent = Rgba([0, 0, 0, 0]);
+        // There is nothing to blend with where the image is transparent
+        for mode in [BlendMode::Normal, BlendMode::Multiply, BlendMode::Screen] {
+            assert_eq!(blend_pixel(&transparent, color, 1.0, mode, false), color);
+        }
+        let black = Rgba([0, 0, 0, 255]);
+        let white = Rgba([255, 255, 255, 255]);
+        assert_eq!(
+            blend_pixel(&black, white, 0.5, BlendMode::Normal, false),
+            Rgba([128, 128, 128, 255])
+        );
+        // Half of white in linear light is lighter in sRGB
+        assert_eq!(
+            blend_pixel(&black, white, 0.5, BlendMode::Normal, true),
+            Rgba([188, 188, 188, 255])
+        );
+        let gray = Rgba([128, 128, 128, 255]);
+        assert_eq!(
+            blend_pixel(&gray, gray, 1.0, BlendMode::Multiply, false),
+            Rgba([64, 64, 64, 255])
+        );
+        let half_black = Rgba([0, 0, 0, 128]);
+        assert_eq!(
+            blend_pixel(&half_black, color, 1.0, BlendMode::Normal, false),
+            Rgba([170, 0, 0, 192])
+        );
+    }
+
+    #[test]
+    fn gamma_round_trip_is_the_identity() {
+        for channel in 0..=255 {
+            for gamma_correct in [false, true] {
+                let value = decode_channel(channel, gamma_correct);
+                assert_eq!(encode_channel(value, gamma_correct), channel);
+            }
+        }
+    }
+
+    #[test]
+    fn non_finite_opacity_leaves_the_image_as_it_is() {
+        let (base, red) = (Rgba([10, 20, 30, 255]), Rgba([255, 0, 0, 255]));
+        for alpha in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            let pixel = blend_pixel(&base, red, alpha, BlendMode::Normal, false);
+            assert_eq!(pixel, base);
+        }
+
+        let image = filled(2, 2, base);
+        let overlay = filled(2, 2, red);
+        let options = ImageOptions {
+            opacity: f32::NAN,
+            ..ImageOptions::default()
+        };
+        let image = image.render_image_with_options(&overlay, 0, 0, &options);
+        assert!(image.rgba_image().pixels().all(|pixel| *pixel == base));
+    }
+}
@@ -0,0 +1,805 @@
+use crate::emoji;
+use crate::{
+    SiColor, SiError, SiFont, TextAlign, TextDirection, TextOptions, TextOverflow, VerticalAlign,
+};
+use ab_glyph::{Font, Glyph, GlyphId, ScaleFont};
+use std::borrow::Cow;
+use std::ops::Range;
+use std::path::PathBuf;
+use unicode_bidi::{Level, ParagraphBidiInfo};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// The font, scale, tracking and color a span of text is rendered with.
+#[derive(Clone)]
+pub(crate) struct TextStyle {
+    pub font: SiFont,
+    pub scale: f32,
+    pub letter_spacing: f32,
+    pub color: SiColor,
+}
+
+impl TextStyle {
+    /// Gets the ascent, descent and line gap of the primary font.
+    fn metrics(&self) -> (f32, f32, f32) {
+        let font = self.font.primary().as_scaled(self.scale);
+        (font.ascent(), font.descent(), font.line_gap())
+    }
+}
+
+/// Text made of spans, each rendered with its own style.
+#[derive(Clone)]
+pub(crate) struct StyledText<'a> {
+    pub text: Cow<'a, str>,
+    /// The offset each span starts at, with the index of its style. The first starts at 0.
+    pub spans: Vec<(usize, usize)>,
+}
+
+impl<'a> StyledText<'a> {
+    /// Creates text rendered entirely with the first style.
+    pub fn plain(text: &'a str) -> StyledText<'a> {
+        StyledText {
+            text: Cow::Borrowed(text),
+            spans: vec![(0, 0)],
+        }
+    }
+
+    /// Gets the index of the style at an offset.
+    fn style_at(&self, offset: usize) -> usize {
+        self.spans
+            .iter()
+            .take_while(|(start, _)| *start <= offset)
+            .last()
+            .map_or(0, |(_, style)| *style)
+    }
+
+    /// Iterates over the non-empty spans, as their range and the index of their style.
+    fn runs(&self) -> impl Iterator<Item = (Range<usize>, usize)> + '_ {
+        self.spans
+            .iter()
+            .enumerate()
+            .filter_map(|(i, (start, style))| {
+                let end = self
+                    .spans
+                    .get(i + 1)
+                    .map_or(self.text.len(), |(end, _)| *end);
+                (end > *start).then_some((*start..end, *style))
+            })
+    }
+
+    /// Gets part of the text, keeping its styles.
+    fn slice(&self, range: Range<usize>) -> StyledText<'_> {
+        let mut spans = vec![(0, self.style_at(range.start))];
+        spans.extend(
+            self.spans
+                .iter()
+                .filter(|(start, _)| *start > range.start && *start < range.end)
+                .map(|(start, style)| (start - range.start, *style)),
+        );
+        StyledText {
+            text: Cow::Borrowed(&self.text[range]),
+            spans,
+        }
+    }
+
+    /// Appends a suffix, in the style of the end of the text.
+    fn with_suffix(&self, suffix: &str) -> StyledText<'static> {
+        let mut spans = self.spans.clone();
+        spans.push((self.text.len(), self.style_at(self.text.len())));
+        StyledText {
+            text: Cow::Owned(format!("{}{}", self.text, suffix)),
+            spans,
+        }
+    }
+
+    /// Gets the largest ascent, descent and line gap of the styles used in the text.
+    fn metrics(&self, styles: &[TextStyle]) -> (f32, f32, f32) {
+        let mut used = self.runs().map(|(_, style)| style).peekable();
+        if used.peek().is_none() {
+            return styles[self.style_at(0)].metrics();
+        }
+        used.map(|style| styles[style].metrics())
+            .fold((f32::MIN, f32::MAX, f32::MIN), |a, b| {
+                (a.0.max(b.0), a.1.min(b.1), a.2.max(b.2))
+            })
+    }
+}
+
+/// Lays out styled text into lines, positioned relative to the top-left corner of its box.
+///
+/// Lines are broken at explicit line breaks (such as `\n`) and, when `max_width` is set,
+/// wrapped at Unicode line break opportunities. Words wider than `max_width` are broken
+/// between characters. When `max_height` is set, lines which don't fit are dropped.
+///
+/// Lines are aligned within `max_width` and `max_height` according to the alignment in
+/// `options`. When either is unset, the text is aligned around that edge of the box instead,
+/// so that e.g. centered text is centered on the X-coordinate of the box.
+///
+/// Each line is as tall as the tallest style on it, and all of its spans share its baseline.
+///
+/// Fails with [`SiError::InvalidArgument`] if `max_width` or `max_height` is negative or not
+/// finite.
+pub(crate) fn layout(
+    text: &StyledText,
+    styles: Vec<TextStyle>,
+    max_width: Option<f32>,
+    max_height: Option<f32>,
+    options: &TextOptions,
+) -> Result<TextLayout, SiError> {
+    for (name, size) in [("max_width", max_width), ("max_height", max_height)] {
+        if let Some(size) = size {
+            if !size.is_finite() || size < 0.0 {
+                return Err(SiError::InvalidArgument(format!(
+                    "{} must be a finite, non-negative number, got {}",
+                    name, size
+                )));
+            }
+        }
+    }
+    if styles.is_empty() {
+        return Ok(TextLayout {
+            lines: Vec::new(),
+            styles,
+            height: 0.0,
+            ascent: 0.0,
+            descent: 0.0,
+            truncated: false,
+        });
+    }
+    // Every line of the text shares the direction of its first strong character
+    let resolved;
+    let options = match options.direction {
+        TextDirection::Auto => {
+            resolved = TextOptions {
+                direction: match unicode_bidi::get_base_direction(text.text.as_ref()) {
+                    unicode_bidi::Direction::Rtl => TextDirection::Rtl,
+                    _ => TextDirection::Ltr,
+                },
+                ..options.clone()
+            };
+            &resolved
+        }
+        _ => options,
+    };
+    let rtl = options.direction == TextDirection::Rtl;
+    let mut wrapped: Vec<(StyledText, bool)> = wrap(text, &styles, max_width, options)
+        .into_iter()
+        .map(|(range, hard_break)| (text.slice(range), hard_break))
+        .collect();
+    let mut max_lines = options.max_lines.unwrap_or(usize::MAX);
+    if let Some(max_height) = max_height {
+        let fitting = line_positions(&wrapped, &styles, options)
+            .iter()
+            .take_while(|(baseline, _, descent)| baseline - descent <= max_height)
+            .count();
+        max_lines = max_lines.min(fitting);
+    }
+    let truncated = wrapped.len() > max_lines;
+    if truncated {
+        match options.overflow {
+            TextOverflow::Clip => wrapped.truncate(max_lines),
+            TextOverflow::Ellipsis => {
+                wrapped.truncate(max_lines);
+                if let Some((line, hard_break)) = wrapped.last_mut() {
+                    *line = ellipsize(line, &styles, max_width, options);
+                    *hard_break = true;
+                }
+            }
+            TextOverflow::Error => {
+                return Err(SiError::Overflow(format!(
+                    "{} lines of text don't fit in {} lines",
+                    wrapped.len(),
+                    max_lines
+                )))
+            }
+        }
+    }
+
+    let positions = line_positions(&wrapped, &styles, options);
+    let (ascent, descent) = match (positions.first(), positions.last()) {
+        (Some(first), Some(last)) => (first.1, last.2),
+        _ => {
+            let (ascent, descent, _) = styles[0].metrics();
+            (ascent, descent)
+        }
+    };
+    let height = positions
+        .last()
+        .map_or(0.0, |(baseline, _, descent)| baseline - descent);
+    let box_height = max_height.unwrap_or(0.0);
+    let top = match options.vertical_align {
+        VerticalAlign::Top => 0.0,
+        VerticalAlign::Middle => (box_height - height) / 2.0,
+        VerticalAlign::Bottom => box_height - height,
+        VerticalAlign::Baseline => -ascent,
+    };
+
+    let box_width = max_width.unwrap_or(0.0);
+    let lines = wrapped
+        .into_iter()
+        .zip(positions)
+        .map(|((line, hard_break), (baseline, _, _))| {
+            let mut layout = layout_line(&line, &styles, options);
+            if options.align == TextAlign::Justify && max_width.is_some() && !hard_break {
+                layout.justify(&line.text, box_width);
+            }
+            layout.x = match (options.align, rtl) {
+                (TextAlign::Left, _)
+                | (TextAlign::Start | TextAlign::Justify, false)
+                | (TextAlign::End, true) => 0.0,
+                (TextAlign::Center, _) => (box_width - layout.width) / 2.0,
+                (TextAlign::Right, _)
+                | (TextAlign::Start | TextAlign::Justify, true)
+                | (TextAlign::End, false) => box_width - layout.width,
+            };
+            layout.baseline = top + baseline;
+            layout
+        })
+        .collect();
+    Ok(TextLayout {
+        lines,
+        styles,
+        height,
+        ascent,
+        descent,
+        truncated,
+    })
+}
+
+/// Gets the baseline of each line, relative to the top of the first line, along with the
+/// ascent and descent of the line.
+fn line_positions(
+    lines: &[(StyledText, bool)],
+    styles: &[TextStyle],
+    options: &TextOptions,
+) -> Vec<(f32, f32, f32)> {
+    let mut positions: Vec<(f32, f32, f32)> = Vec::new();
+    let mut below: Option<f32> = None;
+    let mut baseline = 0.0;
+    for (line, _) in lines {
+        let (ascent, descent, line_gap) = line.metrics(styles);
+        baseline += match below {
+            // The distance between baselines is scaled by the line height
+            Some(below) => (below + ascent) * options.line_height,
+            None => ascent,
+        };
+        positions.push((baseline, ascent, descent));
+        below = Some(line_gap - descent);
+    }
+    positions
+}
+
+/// Lays out a single line of text, with the pen starting at the origin of the baseline.
+///
+/// The line is reordered into runs of a single direction with the Unicode Bidirectional
+/// Algorithm, which are then split into runs of graphemes taken from the same font, each
+/// from the first font in the stack of their style which contains it. The letter spacing
+/// of each style is added between glyphs as extra tracking.
+///
+/// Glyphs are returned in visual order, from left to right.
+pub(crate) fn layout_line(
+    line: &StyledText,
+    styles: &[TextStyle],
+    options: &TextOptions,
+) -> LineLayout {
+    let text: &str = &line.text;
+    // Blank lines have no runs to reorder
+    if text.is_empty() {
+        return LineLayout {
+            glyphs: Vec::new(),
+            width: 0.0,
+            x: 0.0,
+            baseline: 0.0,
+        };
+    }
+    let mut glyphs: Vec<LaidOutGlyph> = Vec::new();
+    let mut caret: f32 = 0.0;
+    let base_level = match options.direction {
+        TextDirection::Ltr => Some(Level::ltr()),
+        TextDirection::Rtl => Some(Level::rtl()),
+        TextDirection::Auto => None,
+    };
+    let bidi = ParagraphBidiInfo::new(text, base_level);
+    let (levels, bidi_runs) = bidi.visual_runs(0..text.len());
+    for bidi_run in bidi_runs {
+        let rtl = levels[bidi_run.start].is_rtl();
+        let mut font_runs = font_runs(&line.slice(bidi_run.clone()), styles);
+        if rtl {
+            font_runs.reverse();
+        }
+        for (start, end, style, source) in font_runs {
+            let (start, end) = (bidi_run.start + start, bidi_run.start + end);
+            let (run, advance) = match source {
+                RunSource::Font(index) => {
+                    layout_run(&text[start..end], start, style, index, styles, rtl, options)
+                }
+                RunSource::Emoji(image) => layout_emoji(start, image, style, styles),
+            };
+            glyphs.extend(run.into_iter().map(|mut laid_out| {
+                laid_out.glyph.position.x += caret;
+                laid_out
+            }));
+            caret += advance;
+        }
+    }
+    // Tracking only goes between glyphs, not after the last one
+    if let Some(last) = glyphs.last() {
+        caret -= styles[last.style].letter_spacing;
+    }
+    LineLayout {
+        glyphs,
+        width: caret,
+        x: 0.0,
+        baseline: 0.0,
+    }
+}
+
+/// Splits text into runs of graphemes which use the same style and font.
+///
+/// Each run is given as its start and end, along with its style and where its glyphs come
+/// from. Emoji found in the emoji directory get a run of their own.
+fn font_runs(text: &StyledText, styles: &[TextStyle]) -> Vec<(usize, usize, usize, RunSource)> {
+    let mut runs: Vec<(usize, usize, usize, RunSource)> = Vec::new();
+    for (span, style) in text.runs() {
+        let font = &styles[style].font;
+        for (start, grapheme) in text.text[span.clone()].grapheme_indices(true) {
+            let start = span.start + start;
+            let end = start + grapheme.len();
+            let image = match &font.emoji_dir {
+                Some(dir) if emoji::is_emoji(grapheme) => emoji::find_emoji_image(dir, grapheme),
+                _ => None,
+            };
+            if let Some(image) = image {
+                runs.push((start, end, style, RunSource::Emoji(image)));
+                continue;
+            }
+            let index = grapheme
+                .chars()
+                .next()
+                .map_or(0, |char| font.font_for(char));
+            match runs.last_mut() {
+                Some((_, run_end, run_style, RunSource::Font(run_index)))
+                    if *run_style == style && *run_index == index =>
+                {
+                    *run_end = end
+                }
+                _ => runs.push((start, end, style, RunSource::Font(index))),
+            }
+        }
+    }
+    runs
+}
+
+/// Lays out an emoji from the emoji directory, as a square as tall as the text.
+fn layout_emoji(
+    offset: usize,
+    image: PathBuf,
+    style: usize,
+    styles: &[TextStyle],
+) -> (Vec<LaidOutGlyph>, f32) {
+    let scale = styles[style].scale;
+    let glyph = LaidOutGlyph {
+        style,
+        font: 0,
+        glyph: GlyphId(0).with_scale_and_position(scale, ab_glyph::point(0.0, 0.0)),
+        cluster: offset,
+        emoji: Some(image),
+    };
+    (vec![glyph], scale + styles[style].letter_spacing)
+}
+
+/// Lays out a run of text from a single font.
+///
+/// Glyphs are positioned relative to the start of the run, with their clusters offset by
+/// `offset`. Returns the glyphs along with the advance of the whole run.
+///
+/// With the `shaping` feature, the run is shaped with rustybuzz. Otherwise, each character
+/// is mapped to a single glyph, and kerned against the previous one. Right-to-left runs
+/// are laid out in reverse, one grapheme at a time.
+fn layout_run(
+    run: &str,
+    offset: usize,
+    style: usize,
+    index: usize,
+    styles: &[TextStyle],
+    rtl: bool,
+    options: &TextOptions,
+) -> (Vec<LaidOutGlyph>, f32) {
+    #[cfg(feature = "shaping")]
+    if let Some(shaped) = shape_run(run, offset, style, index, styles, rtl, options) {
+        return shaped;
+    }
+
+    let TextStyle {
+        font: stack,
+        scale,
+        letter_spacing,
+        ..
+    } = &styles[style];
+    let font = stack.fonts[index].as_scaled(*scale);
+    let mut glyphs: Vec<LaidOutGlyph> = Vec::new();
+    let mut caret: f32 = 0.0;
+    let mut previous: Option<GlyphId> = None;
+    let mut graphemes: Vec<(usize, &str)> = run.grapheme_indices(true).collect();
+    if rtl {
+        graphemes.reverse();
+    }
+    for (start, grapheme) in graphemes {
+        let count = glyphs.len();
+        for (i, char) in grapheme.char_indices() {
+            // Without shaping, joiners and variation selectors would render as missing glyphs
+            if matches!(char, '\u{200C}' | '\u{200D}' | '\u{FE00}'..='\u{FE0F}') {
+                continue;
+            }
+            let id = font.glyph_id(char);
+            if let Some(previous) = previous {
+                caret += font.kern(previous, id);
+            }
+            glyphs.push(LaidOutGlyph {
+                style,
+                font: index,
+                glyph: id.with_scale_and_position(*scale, ab_glyph::point(caret, 0.0)),
+                cluster: offset + start + i,
+                emoji: None,
+            });
+            caret += match options.space_width {
+                Some(width) if char.is_whitespace() => width,
+                _ => font.h_advance(id),
+            };
+            previous = Some(id);
+        }
+        // Tracking is added between graphemes, so that combining marks stay on their base
+        if glyphs.len() > count {
+            caret += letter_spacing;
+        }
+    }
+    (glyphs, caret)
+}
+
+/// Lays out a run of text from a single font, shaping it with rustybuzz.
+///
+/// Returns `None` if rustybuzz can't read the font.
+#[cfg(feature = "shaping")]
+fn shape_run(
+    run: &str,
+    offset: usize,
+    style: usize,
+    index: usize,
+    styles: &[TextStyle],
+    rtl: bool,
+    options: &TextOptions,
+) -> Option<(Vec<LaidOutGlyph>, f32)> {
+    let TextStyle {
+        font: stack,
+        scale,
+        letter_spacing,
+        ..
+    } = &styles[style];
+    let font = stack.fonts[index].as_scaled(*scale);
+    let face = stack.fonts[index].shaping_face()?;
+    let mut buffer = rustybuzz::UnicodeBuffer::new();
+    buffer.push_str(run);
+    buffer.guess_segment_properties();
+    buffer.set_direction(if rtl {
+        rustybuzz::Direction::RightToLeft
+    } else {
+        rustybuzz::Direction::LeftToRight
+    });
+    let shaped = rustybuzz::shape(&face, &[], buffer);
+
+    let mut glyphs: Vec<LaidOutGlyph> = Vec::new();
+    let mut caret: f32 = 0.0;
+    let infos = shaped.glyph_infos();
+    for (i, (info, position)) in infos.iter().zip(shaped.glyph_positions()).enumerate() {
+        let cluster = info.cluster as usize;
+        let point = ab_glyph::point(
+            caret + position.x_offset as f32 * font.h_scale_factor(),
+            -position.y_offset as f32 * font.v_scale_factor(),
+        );
+        glyphs.push(LaidOutGlyph {
+            style,
+            font: index,
+            glyph: GlyphId(info.glyph_id as u16).with_scale_and_position(*scale, point),
+            cluster: offset + cluster,
+            emoji: None,
+        });
+        let whitespace = run[cluster..].starts_with(char::is_whitespace);
+        caret += match options.space_width {
+            Some(width) if whitespace => width,
+            _ => position.x_advance as f32 * font.h_scale_factor(),
+        };
+        // Tracking is added once per cluster, so that marks stay on their base and joined
+        // letters stay joined
+        let same_cluster = matches!(infos.get(i + 1), Some(next) if next.cluster == info.cluster);
+        if !same_cluster {
+            caret += letter_spacing;
+        }
+    }
+    Some((glyphs, caret))
+}
+
+/// Shortens a line on grapheme boundaries until it fits within `max_width` with the
+/// ellipsis from `options` appended.
+fn ellipsize(
+    line: &StyledText,
+    styles: &[TextStyle],
+    max_width: Option<f32>,
+    options: &TextOptions,
+) -> StyledText<'static> {
+    let mut end = line.text.len();
+    loop {
+        let kept = line.text[..end].trim_end().len();
+        let shortened = line.slice(0..kept).with_suffix(&options.ellipsis);
+        let fits = match max_width {
+            Some(max_width) => layout_line(&shortened, styles, options).width <= max_width,
+            None => true,
+        };
+        if fits || end == 0 {
+            return shortened;
+        }
+        end = line.text[..end]
+            .grapheme_indices(true)
+            .next_back()
+            .map_or(0, |(i, _)| i);
+    }
+}
+
+/// Splits text into the lines it should be rendered on.
+///
+/// Each line is given as its range in the text, paired with whether it ends in an explicit
+/// line break (or the end of the text), rather than having been wrapped.
+fn wrap(
+    text: &StyledText,
+    styles: &[TextStyle],
+    max_width: Option<f32>,
+    options: &TextOptions,
+) -> Vec<(Range<usize>, bool)> {
+    let full: &str = &text.text;
+    let trimmed = |start: usize, end: usize| start..start + full[start..end].trim_end().len();
+    let fits = |range: Range<usize>| match max_width {
+        Some(max_width) => layout_line(&text.slice(range), styles, options).width <= max_width,
+        None => true,
+    };
+    let mut lines: Vec<(Range<usize>, bool)> = Vec::new();
+    let mut start = 0;
+    let mut last_break = 0;
+    for (index, opportunity) in unicode_linebreak::linebreaks(full) {
+        if last_break > start && !fits(trimmed(start, index)) {
+            lines.push((trimmed(start, last_break), false));
+            start = last_break;
+        }
+        // A single word which is too wide on its own is broken between graphemes
+        while start < index && !fits(trimmed(start, index)) {
+            let end = full[start..index]
+                .grapheme_indices(true)
+                .skip(1)
+                .map(|(i, _)| start + i)
+                .take_while(|end| fits(start..*end))
+                .last()
+                .unwrap_or_else(|| {
+                    // Not even a single grapheme fits, so it goes on a line of its own
+                    full[start..index]
+                        .grapheme_indices(true)
+                        .nth(1)
+                        .map_or(index, |(i, _)| start + i)
+                });
+            if end >= index {
+                // The last grapheme stays on the current line
+                break;
+            }
+            lines.push((start..end, false));
+            start = end;
+        }
+        last_break = index;
+        if opportunity == unicode_linebreak::BreakOpportunity::Mandatory {
+            lines.push((trimmed(start, index), true));
+            start = index;
+        }
+    }
+    lines
+}
+
+/// A glyph, along with its style and the index of the font in the stack of its style.
+pub(crate) struct LaidOutGlyph {
+    pub style: usize,
+    pub font: usize,
+    pub glyph: Glyph,
+    /// Byte offset, into the text of its line, of the characters the glyph was made from.
+    pub cluster: usize,
+    /// The image to draw instead of the glyph, for emoji from the emoji directory.
+    pub emoji: Option<PathBuf>,
+}
+
+/// Where the glyphs of a run of text come from.
+enum RunSource {
+    /// The font at an index in the stack.
+    Font(usize),
+    /// An image from the emoji directory.
+    Emoji(PathBuf),
+}
+
+/// A line of text, with glyphs positioned relative to the start of its baseline.
+pub(crate) struct LineLayout {
+    pub glyphs: Vec<LaidOutGlyph>,
+    pub width: f32,
+    /// Offset of the start of the line from the left edge of the box.
+    pub x: f32,
+    /// Offset of the baseline from the top edge of the box.
+    pub baseline: f32,
+}
+
+impl LineLayout {
+    /// Stretches the spaces between words so that the line is `width` wide.
+    fn justify(&mut self, text: &str, width: f32) {
+        let leading = text.len() - text.trim_start().len();
+        let is_gap = |glyph: &LaidOutGlyph| {
+            glyph.cluster >= leading && text[glyph.cluster..].starts_with(char::is_whitespace)
+        };
+        let gaps = self.glyphs.iter().filter(|glyph| is_gap(glyph)).count();
+        if gaps == 0 || width <= self.width {
+            return;
+        }
+        let extra = (width - self.width) / gaps as f32;
+        // Glyphs are in visual order, so each one moves over by the gaps to its left
+        let mut shift = 0.0;
+        for glyph in &mut self.glyphs {
+            glyph.glyph.position.x += shift;
+            if is_gap(glyph) {
+                shift += extra;
+            }
+        }
+        self.width = width;
+    }
+}
+
+/// Text which has been laid out into lines.
+pub(crate) struct TextLayout {
+    pub lines: Vec<LineLayout>,
+    /// The styles the glyphs refer to.
+    pub styles: Vec<TextStyle>,
+    /// Distance from the top of the first line to the bottom of the last line.
+    pub height: f32,
+    /// The ascent of the first line.
+    pub ascent: f32,
+    /// The descent of the last line.
+    pub descent: f32,
+    /// Whether lines were dropped because they didn't fit.
+    pub truncated: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn font() -> SiFont {
+        SiFont::from_vec(include_bytes!("../tests/fonts/DejaVuSansMono.ttf").to_vec()).unwrap()
+    }
+
+    fn layout_text(
+        text: &str,
+        max_width: Option<f32>,
+        max_height: Option<f32>,
+        options: &TextOptions,
+    ) -> Result<TextLayout, SiError> {
+        let styles = vec![font().style(20.0, SiColor::BLACK, options)];
+        let text = StyledText::plain(text);
+        layout(&text, styles, max_width, max_height, options)
+    }
+
+    #[test]
+    fn blank_lines_are_kept() {
+        let options = TextOptions::default();
+        let layout = layout_text("Title\n\nSubtitle", None, None, &options).unwrap();
+        assert_eq!(layout.lines.len(), 3);
+        assert!(layout.lines[1].glyphs.is_empty());
+        assert_eq!(layout.lines[1].width, 0.0);
+        assert!(layout.lines[2].baseline > layout.lines[1].baseline);
+
+        let layout = layout_text("\n", None, None, &options).unwrap();
+        assert_eq!(layout.lines.len(), 1);
+        assert!(layout.lines[0].glyphs.is_empty());
+    }
+
+    #[test]
+    fn trailing_line_break_does_not_add_a_line() {
+        let options = TextOptions::default();
+        let layout = layout_text("Title\n", None, None, &options).unwrap();
+        assert_eq!(layout.lines.len(), 1);
+        assert_eq!(layout.lines[0].glyphs.len(), 5);
+    }
+
+    #[test]
+    fn words_wrap_to_the_width() {
+        let options = TextOptions::default();
+        let layout = layout_text("one two three four", Some(100.0), None, &options).unwrap();
+        let lengths: Vec<usize> = layout.lines.iter().map(|line| line.glyphs.len()).collect();
+        assert_eq!(lengths, vec![7, 5, 4]);
+        assert!(layout.lines.iter().all(|line| line.width <= 100.0));
+        assert!(!layout.truncated);
+    }
+
+    #[test]
+    fn overflowing_lines_are_ellipsized() {
+        let mut options = TextOptions {
+            max_lines: Some(1),
+            overflow: TextOverflow::Ellipsis,
+            ..TextOptions::default()
+        };
+        let layout = layout_text("one two three four", Some(100.0), None, &options).unwrap();
+        assert!(layout.truncated);
+        assert_eq!(layout.lines.len(), 1);
+        // "one two…", with the ellipsis after the kept words
+        let line = &layout.lines[0];
+        assert_eq!(line.glyphs.len(), 8);
+        assert_eq!(line.glyphs.last().unwrap().cluster, "one two".len());
+        assert!(line.width <= 100.0);
+
+        options.overflow = TextOverflow::Clip;
+        let layout = layout_text("one two three four", Some(100.0), None, &options).unwrap();
+        assert!(layout.truncated);
+        assert_eq!(layout.lines[0].glyphs.len(), 7);
+
+        options.overflow = TextOverflow::Error;
+        assert!(matches!(
+            layout_text("one two three four", Some(100.0), None, &options),
+            Err(SiError::Overflow(_))
+        ));
+    }
+
+    #[test]
+    fn whitespace_only_text_is_an_empty_line() {
+        let options = TextOptions::default();
+        let layout = layout_text(" ", None, None, &options).unwrap();
+        assert_eq!(layout.lines.len(), 1);
+        assert!(layout.lines[0].glyphs.is_empty());
+        assert!(layout_text(" ", Some(100.0), None, &options).is_ok());
+    }
+
+    #[test]
+    fn invalid_box_sizes_are_rejected() {
+        let options = TextOptions::default();
+        for size in [f32::NAN, f32::INFINITY, -1.0] {
+            assert!(matches!(
+                layout_text("Hello world", Some(size), None, &options),
+                Err(SiError::InvalidArgument(_))
+            ));
+            assert!(matches!(
+                layout_text("Hello world", Some(100.0), Some(size), &options),
+                Err(SiError::InvalidArgument(_))
+            ));
+        }
+        // A box too narrow for any grapheme puts each on a line of its own
+        let layout = layout_text("abc", Some(0.0), None, &options).unwrap();
+        assert_eq!(layout.lines.len(), 3);
+    }
+
+    #[test]
+    fn letter_spacing_is_added_once_per_grapheme() {
+        let tracking = |text: &str| {
+            let width = |letter_spacing: f32| {
+                let options = TextOptions {
+                    letter_spacing,
+                    ..TextOptions::default()
+                };
+                layout_text(text, None, None, &options).unwrap().lines[0].width
+            };
+            width(10.0) - width(0.0)
+        };
+        // "e" with a combining acute accent is a single grapheme
+        assert_eq!(tracking("e\u{301}"), 0.0);
+        assert_eq!(tracking("ab"), 10.0);
+    }
+
+    #[test]
+    fn rtl_text_is_aligned_to_the_right_by_default() {
+        let options = TextOptions::default();
+        // "Shalom" in Hebrew
+        let text = "\u{5e9}\u{5dc}\u{5d5}\u{5dd}";
+        let layout = layout_text(text, Some(200.0), None, &options).unwrap();
+        let line = &layout.lines[0];
+        assert!(line.width > 0.0);
+        assert_eq!(line.x, 200.0 - line.width);
+        // Glyphs are in visual order, so the first character is drawn last
+        let clusters: Vec<usize> = line.glyphs.iter().map(|glyph| glyph.cluster).collect();
+        assert_eq!(clusters, vec![6, 4, 2, 0]);
+    }
+}
@@ -1,7 +1,23 @@
+mod color;
+mod emoji;
+mod error;
 mod font;
 mod image;
+mod layout;
 mod macros;
+mod markup;
+mod mask;
+mod paint;
 mod preset;
+mod registry;
+mod rich;
+#[cfg(feature = "system-fonts")]
+mod system;
+pub use crate::color::*;
+pub use crate::error::*;
 pub use crate::font::*;
 pub use crate::image::*;
+pub use crate::paint::*;
 pub use crate::preset::*;
+pub use crate::registry::*;
+pub use crate::rich::*;
@@ -6,18 +6,23 @@ macro_rules! preset {
                     let $key = match vals.get(stringify!($key)) {
                         Some($key) => {
                             // Do type checking
-                            if $key.type_id() == std::any::TypeId::of::<$type>() {
-                                // Downcast it
-                                $key.downcast_ref::<$type>().unwrap()
-                            } else {
-                                panic!("Expected type: {:?}\nFound type: {:?}", std::any::TypeId::of::<$type>(), $key.type_id());
+                            match $key.downcast_ref::<$type>() {
+                                Some($key) => $key,
+                                None => return Err($crate::SiError::PresetArgument(format!(
+                                    "Expected {} to be of type {}",
+                                    stringify!($key),
+                                    stringify!($type)
+                                ))),
                             }
                         }
-                        None => panic!("No {} provided", stringify!($key))
+                        None => return Err($crate::SiError::PresetArgument(format!(
+                            "No {} provided",
+                            stringify!($key)
+                        ))),
                     };
                 )*;
                 let $img = img.clone();
-                $($body)*
+                Ok({ $($body)* })
             }
             ));
     }
@@ -39,6 +44,6 @@ macro_rules! anymap {
 #[macro_export]
 macro_rules! render {
     ($image:ident: $text:expr; $x:expr, $y:expr; "font" $font:expr, "scale" $scale:expr, "opts" $opts:expr, "color" $color:expr) => {
-        $image = $image.render_text($text, $scale, $x, $y, $color, $font, $opts);
+        $image = $image.render_text($text, $scale, $x, $y, $color, $font, $opts)?;
     };
 }
@@ -0,0 +1,218 @@
+use wasm_bindgen::prelude::*;
+
+use crate::color::*;
+use crate::error::*;
+use crate::font::*;
+use crate::rich::*;
+
+/// The style of a run of marked up text.
+#[derive(Clone, Debug, PartialEq)]
+struct MarkupStyle {
+    bold: bool,
+    italic: bool,
+    color: Option<SiColor>,
+}
+
+#[wasm_bindgen]
+impl SiRichText {
+    /// Creates rich text from a small inline markup, like [`SiRichText::from_markup`], with
+    /// the color in CSS syntax.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SiError::Color`] if the color is not valid.
+    #[wasm_bindgen(js_name = "fromMarkup")]
+    pub fn js_from_markup(
+        markup: &str,
+        family: &SiFontFamily,
+        scale: f32,
+        color: Option<String>,
+    ) -> Result<SiRichText, SiError> {
+        let color = parse_optional(color)?;
+        Ok(SiRichText::from_markup(markup, family, scale, color))
+    }
+}
+
+impl SiRichText {
+    /// Creates rich text from a small inline markup.
+    ///
+    /// The markup supports:
+    ///
+    /// * `**bold**` and `*italic*` text, rendered with the variants of `family`.
+    /// * `[color=#f00]colored[/color]` text, which can be nested.
+    /// * `\` to escape the next character, so that e.g. `\*` renders a literal `*`.
+    ///
+    /// Markers which are never closed apply until the end of the text. A `[color=…]` tag
+    /// whose color can't be parsed with [`SiColor::parse`], and a `[/color]` without a
+    /// matching `[color=…]`, are rendered as they are.
+    ///
+    /// # Arguments
+    ///
+    /// * `markup` - The marked up text.
+    /// * `family` - The SiFontFamily whose variants the text is rendered with.
+    /// * `scale` - The scale of the rendered text.
+    /// * `color` - The color of text outside of color tags, or `None` for black.
+    ///
+    /// # Returns
+    ///
+    /// A new SiRichText, with a span for each run of text in the same style
+    pub fn from_markup(
+        markup: &str,
+        family: &SiFontFamily,
+        scale: f32,
+        color: Option<SiColor>,
+    ) -> SiRichText {
+        parse(markup, color)
+            .into_iter()
+            .fold(SiRichText::new(), |rich_text, (text, style)| {
+                let font = family.variant(style.bold, style.italic);
+                rich_text.with_span(&TextSpan::new(&text, &font, scale, style.color))
+            })
+    }
+}
+
+/// Parses inline markup into runs of text in the same style.
+fn parse(markup: &str, color: Option<SiColor>) -> Vec<(String, MarkupStyle)> {
+    let mut runs: Vec<(String, MarkupStyle)> = Vec::new();
+    let mut style = MarkupStyle {
+        bold: false,
+        italic: false,
+        color,
+    };
+    // The colors to go back to when color tags are closed
+    let mut colors: Vec<Option<SiColor>> = Vec::new();
+    let mut rest = markup;
+    while let Some(char) = rest.chars().next() {
+        if let Some(after) = rest.strip_prefix("**") {
+            style.bold = !style.bold;
+            rest = after;
+            continue;
+        }
+        if let Some(after) = rest.strip_prefix('*') {
+            style.italic = !style.italic;
+            rest = after;
+            continue;
+        }
+        if let Some(after) = rest.strip_prefix("[color=") {
+            if let Some(end) = after.find(']') {
+                if let Ok(color) = SiColor::parse(&after[..end]) {
+                    colors.push(style.color.replace(color));
+                    rest = &after[end + 1..];
+                    continue;
+                }
+            }
+        }
+        if let Some(after) = rest.strip_prefix("[/color]") {
+            if let Some(color) = colors.pop() {
+                style.color = color;
+                rest = after;
+                continue;
+            }
+        }
+
+        let mut literal = char;
+        rest = &rest[char.len_utf8()..];
+        if char == '\\' {
+            if let Some(escaped) = rest.chars().next() {
+                literal = escaped;
+                rest = &rest[escaped.len_utf8()..];
+            }
+        }
+        match runs.last_mut() {
+            Some((text, run_style)) if *run_style == style => text.push(literal),
+            _ => runs.push((literal.to_string(), style.clone())),
+        }
+    }
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses markup into its runs, as their text, boldness, italicness and hex color.
+    fn runs(markup: &str) -> Vec<(String, bool, bool, Option<String>)> {
+        parse(markup, None)
+            .into_iter()
+            .map(|(text, style)| {
+                let color = style.color.map(|color| color.to_hex());
+                (text, style.bold, style.italic, color)
+            })
+            .collect()
+    }
+
+    fn run(
+        text: &str,
+        bold: bool,
+        italic: bool,
+        color: Option<&str>,
+    ) -> (String, bool, bool, Option<String>) {
+        (text.to_string(), bold, italic, color.map(str::to_string))
+    }
+
+    #[test]
+    fn bold_and_italic_markers_toggle() {
+        assert_eq!(
+            runs("a **b** *c* ***d***"),
+            vec![
+                run("a ", false, false, None),
+                run("b", true, false, None),
+                run(" ", false, false, None),
+                run("c", false, true, None),
+                run(" ", false, false, None),
+                run("d", true, true, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn color_tags_nest() {
+        assert_eq!(
+            runs("[color=red]a[color=#00f]b[/color]c[/color]d[/color]"),
+            vec![
+                run("a", false, false, Some("#ff0000")),
+                run("b", false, false, Some("#0000ff")),
+                run("c", false, false, Some("#ff0000")),
+                run("d[/color]", false, false, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_color_tags_are_literal() {
+        assert_eq!(
+            runs("[color=garbage]a[/color]"),
+            vec![run("[color=garbage]a[/color]", false, false, None)]
+        );
+        assert_eq!(
+            runs("[color=red"),
+            vec![run("[color=red", false, false, None)]
+        );
+    }
+
+    #[test]
+    fn backslashes_escape_the_next_character() {
+        assert_eq!(runs("\\*a\\*"), vec![run("*a*", false, false, None)]);
+        assert_eq!(runs("a\\"), vec![run("a\\", false, false, None)]);
+        assert_eq!(
+            runs("\\[color=red]"),
+            vec![run("[color=red]", false, false, None)]
+        );
+    }
+
+    #[test]
+    fn unclosed_markers_apply_until_the_end() {
+        assert_eq!(
+            runs("a **b *c"),
+            vec![
+                run("a ", false, false, None),
+                run("b ", true, false, None),
+                run("c", true, true, None),
+            ]
+        );
+        assert_eq!(
+            runs("[color=red]a"),
+            vec![run("a", false, false, Some("#ff0000"))]
+        );
+    }
+}
@@ -0,0 +1,281 @@
+use ab_glyph::OutlinedGlyph;
+
+/// A coverage mask: how much of each pixel in a region of an image is covered by a shape,
+/// from 0 to 1.
+#[derive(Clone)]
+pub(crate) struct Mask {
+    pub x: i64,
+    pub y: i64,
+    pub width: usize,
+    pub height: usize,
+    pub data: Vec<f32>,
+}
+
+impl Mask {
+    /// Creates an empty mask covering a region of an image.
+    pub fn new(x: i64, y: i64, width: usize, height: usize) -> Mask {
+        Mask {
+            x,
+            y,
+            width,
+            height,
+            data: vec![0.0; width * height],
+        }
+    }
+
+    /// Adds the coverage of a glyph to the mask.
+    pub fn add_glyph(&mut self, glyph: &OutlinedGlyph) {
+        let bb = glyph.px_bounds();
+        glyph.draw(|_x, _y, v| {
+            let x = _x as i64 + bb.min.x as i64 - self.x;
+            let y = _y as i64 + bb.min.y as i64 - self.y;
+            if x >= 0 && y >= 0 && x < self.width as i64 && y < self.height as i64 {
+                let i = y as usize * self.width + x as usize;
+                self.data[i] = (self.data[i] + v).min(1.0);
+            }
+        });
+    }
+
+    /// Iterates over the covered pixels, as image positions and coverage.
+    pub fn pixels(&self) -> impl Iterator<Item = (i64, i64, f32)> + '_ {
+        self.data
+            .iter()
+            .enumerate()
+            .filter(|(_, v)| **v > 0.0)
+            .map(|(i, v)| {
+                (
+                    self.x + (i % self.width) as i64,
+                    self.y + (i / self.width) as i64,
+                    *v,
+                )
+            })
+    }
+
+    /// Grows the shape by `radius` pixels in every direction.
+    pub fn dilate(&self, radius: f32) -> Mask {
+        self.spread(radius, false)
+    }
+
+    /// Shrinks the shape by `radius` pixels in every direction.
+    pub fn erode(&self, radius: f32) -> Mask {
+        self.spread(radius, true)
+    }
+
+    /// Removes the coverage of another mask over the same region from this one.
+    pub fn subtract(&self, other: &Mask) -> Mask {
+        let mut mask = self.clone();
+        for (v, o) in mask.data.iter_mut().zip(&other.data) {
+            *v = (*v - o).max(0.0);
+        }
+        mask
+    }
+
+    /// Combines the coverage of another mask over the same region with this one.
+    pub fn union(&self, other: &Mask) -> Mask {
+        let mut mask = self.clone();
+        for (v, o) in mask.data.iter_mut().zip(&other.data) {
+            *v = v.max(*o);
+        }
+        mask
+    }
+
+    /// Moves the mask by a number of pixels, which may be fractional.
+    ///
+    /// Fractions of a pixel are interpolated, so that the coverage is spread over the pixels
+    /// the shape now straddles. Offsets which aren't finite are ignored.
+    pub fn offset(&self, dx: f32, dy: f32) -> Mask {
+        let finite = |d: f32| if d.is_finite() { d } else { 0.0 };
+        let (dx, dy) = (finite(dx), finite(dy));
+        let (fx, fy) = (dx - dx.floor(), dy - dy.floor());
+        let (x, y) = (self.x + dx.floor() as i64, self.y + dy.floor() as i64);
+        if fx == 0.0 && fy == 0.0 {
+            return Mask {
+                x,
+                y,
+                ..self.clone()
+            };
+        }
+        let mut mask = Mask::new(x, y, self.width + 1, self.height + 1);
+        let weights = [
+            (0, 0, (1.0 - fx) * (1.0 - fy)),
+            (1, 0, fx * (1.0 - fy)),
+            (0, 1, (1.0 - fx) * fy),
+            (1, 1, fx * fy),
+        ];
+        for (i, v) in self.data.iter().enumerate().filter(|(_, v)| **v > 0.0) {
+            let (x, y) = (i % self.width, i / self.width);
+            for (dx, dy, weight) in weights {
+                let j = (y + dy) * mask.width + x + dx;
+                mask.data[j] = (mask.data[j] + v * weight).min(1.0);
+            }
+        }
+        mask
+    }
+
+    /// Blurs the mask with an approximate Gaussian blur, growing it to fit the blur.
+    ///
+    /// Like CSS, the standard deviation of the blur is half of `radius`.
+    pub fn blur(&self, radius: f32) -> Mask {
+        // Three box blurs, with a combined variance of sigma^2
+        let sigma = radius.max(0.0) / 2.0;
+        let box_radius = (((4.0 * sigma * sigma + 1.0).sqrt() - 1.0) / 2.0).round() as usize;
+        if box_radius == 0 {
+            return self.clone();
+        }
+        let grow = 3 * box_radius;
+        let mut mask = Mask::new(
+            self.x - grow as i64,
+            self.y - grow as i64,
+            self.width + 2 * grow,
+            self.height + 2 * grow,
+        );
+        for y in 0..self.height {
+            let start = (y + grow) * mask.width + grow;
+            mask.data[start..start + self.width]
+                .copy_from_slice(&self.data[y * self.width..(y + 1) * self.width]);
+        }
+        for _ in 0..3 {
+            mask.box_blur(box_radius);
+        }
+        mask
+    }
+
+    /// Blurs the mask horizontally and vertically with a box of `2 * radius + 1` pixels.
+    fn box_blur(&mut self, radius: usize) {
+        let (width, height) = (self.width, self.height);
+        let size = (2 * radius + 1) as f32;
+        let blur_line = |line: &[f32]| -> Vec<f32> {
+            let mut sums = vec![0.0f32; line.len() + 1];
+            for (i, v) in line.iter().enumerate() {
+                sums[i + 1] = sums[i] + v;
+            }
+            (0..line.len())
+                .map(|i| {
+                    let end = (i + radius + 1).min(line.len());
+                    let start = i.saturating_sub(radius);
+                    ((sums[end] - sums[start]) / size).clamp(0.0, 1.0)
+                })
+                .collect()
+        };
+        for y in 0..height {
+            let row = blur_line(&self.data[y * width..(y + 1) * width]);
+            self.data[y * width..(y + 1) * width].copy_from_slice(&row);
+        }
+        for x in 0..width {
+            let column: Vec<f32> = (0..height).map(|y| self.data[y * width + x]).collect();
+            for (y, v) in blur_line(&column).into_iter().enumerate() {
+                self.data[y * width + x] = v;
+            }
+        }
+    }
+
+    /// Dilates the mask, or its inverse when eroding, with an anti-aliased disc.
+    ///
+    /// Pixels at least half covered are the inside of the shape; every other pixel is covered
+    /// by how far it is within `radius` of the nearest of them.
+    fn spread(&self, radius: f32, invert: bool) -> Mask {
+        let (width, height) = (self.width, self.height);
+        let value = |v: f32| if invert { 1.0 - v } else { v };
+        let mut distances: Vec<f64> = self
+            .data
+            .iter()
+            .map(|v| if value(*v) >= 0.5 { 0.0 } else { f64::INFINITY })
+            .collect();
+        for y in 0..height {
+            let row = squared_distances(&distances[y * width..(y + 1) * width]);
+            distances[y * width..(y + 1) * width].copy_from_slice(&row);
+        }
+        for x in 0..width {
+            let column: Vec<f64> = (0..height).map(|y| distances[y * width + x]).collect();
+            for (y, d) in squared_distances(&column).into_iter().enumerate() {
+                distances[y * width + x] = d;
+            }
+        }
+        let data = self
+            .data
+            .iter()
+            .zip(distances)
+            .map(|(v, d)| {
+                let coverage = (radius + 1.0 - d.sqrt() as f32).clamp(0.0, 1.0);
+                value(value(*v).max(coverage))
+            })
+            .collect();
+        Mask { data, ..*self }
+    }
+}
+
+/// Computes the squared distance from every point of a line to the nearest point, given the
+/// squared distances already known at each point (Felzenszwalb and Huttenlocher).
+fn squared_distances(line: &[f64]) -> Vec<f64> {
+    // The lower envelope of the parabolas rooted at each point, as the points and where
+    // along the line their parabola starts being the lowest
+    let mut envelope: Vec<(usize, f64)> = Vec::new();
+    for (q, fq) in line.iter().enumerate().filter(|(_, f)| f.is_finite()) {
+        let qf = q as f64;
+        while let Some(&(p, start)) = envelope.last() {
+            let pf = p as f64;
+            let s = ((fq + qf * qf) - (line[p] + pf * pf)) / (2.0 * (qf - pf));
+            if s > start {
+                envelope.push((q, s));
+                break;
+            }
+            envelope.pop();
+        }
+        if envelope.is_empty() {
+            envelope.push((q, f64::NEG_INFINITY));
+        }
+    }
+    if envelope.is_empty() {
+        return line.to_vec();
+    }
+    let mut k = 0;
+    (0..line.len())
+        .map(|x| {
+            let xf = x as f64;
+            while k + 1 < envelope.len() && envelope[k + 1].1 <= xf {
+                k += 1;
+            }
+            let p = envelope[k].0;
+            (xf - p as f64).powi(2) + line[p]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fractional_offsets_are_interpolated() {
+        let mut mask = Mask::new(0, 0, 2, 1);
+        mask.data = vec![1.0, 0.5];
+        let moved = mask.offset(1.25, -0.5);
+        assert_eq!((moved.x, moved.y, moved.width, moved.height), (1, -1, 3, 2));
+        assert_eq!(
+            moved.data,
+            vec![0.375, 0.3125, 0.0625, 0.375, 0.3125, 0.0625]
+        );
+        assert_eq!(moved.data.iter().sum::<f32>(), 1.5);
+
+        let moved = mask.offset(2.0, f32::NAN);
+        assert_eq!((moved.x, moved.y, moved.width), (2, 0, 2));
+        assert_eq!(moved.data, mask.data);
+    }
+
+    #[test]
+    fn dilate_and_erode_follow_the_distance_to_the_shape() {
+        let mut mask = Mask::new(0, 0, 21, 21);
+        mask.data[10 * 21 + 10] = 1.0;
+        let dilated = mask.dilate(3.0);
+        for y in 0..21 {
+            for x in 0..21 {
+                let d = ((x as f32 - 10.0).powi(2) + (y as f32 - 10.0).powi(2)).sqrt();
+                let expected = (4.0 - d).clamp(0.0, 1.0);
+                assert!((dilated.data[y * 21 + x] - expected).abs() < 1e-5);
+            }
+        }
+        let eroded = dilated.erode(3.0);
+        assert_eq!(eroded.data.iter().filter(|v| **v >= 0.5).count(), 1);
+        assert_eq!(mask.erode(1.0).data, vec![0.0; 21 * 21]);
+    }
+}
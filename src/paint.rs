@@ -0,0 +1,218 @@
+use image::{Rgba, RgbaImage};
+use wasm_bindgen::prelude::*;
+
+use crate::color::*;
+use crate::error::*;
+use crate::image::*;
+
+/// Represents how text is filled: with a solid color, a gradient or an image pattern.
+///
+/// Positions are in pixels, relative to the position the text is rendered at.
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct SiPaint {
+    kind: PaintKind,
+    stops: Vec<(f32, Rgba<u8>)>,
+}
+
+#[derive(Clone)]
+enum PaintKind {
+    Solid(Rgba<u8>),
+    LinearGradient { start: (f32, f32), end: (f32, f32) },
+    RadialGradient { center: (f32, f32), radius: f32 },
+    Pattern(RgbaImage),
+}
+
+#[wasm_bindgen]
+impl SiPaint {
+    /// Creates a paint with a single color, like [`SiPaint::solid`], with the color in CSS
+    /// syntax.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SiError::Color`] if the color is not valid.
+    #[wasm_bindgen(js_name = "solid")]
+    pub fn js_solid(color: &str) -> Result<SiPaint, SiError> {
+        Ok(SiPaint::solid(SiColor::parse(color)?))
+    }
+
+    /// Creates a linear gradient between two points. Colors are added with [`SiPaint::with_stop`].
+    ///
+    /// # Arguments
+    ///
+    /// * `x0` - The X-coordinate of the start of the gradient.
+    /// * `y0` - The Y-coordinate of the start of the gradient.
+    /// * `x1` - The X-coordinate of the end of the gradient.
+    /// * `y1` - The Y-coordinate of the end of the gradient.
+    #[wasm_bindgen(js_name = "linearGradient")]
+    pub fn linear_gradient(x0: f32, y0: f32, x1: f32, y1: f32) -> SiPaint {
+        SiPaint {
+            kind: PaintKind::LinearGradient {
+                start: (x0, y0),
+                end: (x1, y1),
+            },
+            stops: Vec::new(),
+        }
+    }
+
+    /// Creates a radial gradient around a point. Colors are added with [`SiPaint::with_stop`].
+    ///
+    /// # Arguments
+    ///
+    /// * `cx` - The X-coordinate of the center of the gradient.
+    /// * `cy` - The Y-coordinate of the center of the gradient.
+    /// * `radius` - The distance from the center at which the gradient ends.
+    #[wasm_bindgen(js_name = "radialGradient")]
+    pub fn radial_gradient(cx: f32, cy: f32, radius: f32) -> SiPaint {
+        SiPaint {
+            kind: PaintKind::RadialGradient {
+                center: (cx, cy),
+                radius,
+            },
+            stops: Vec::new(),
+        }
+    }
+
+    /// Creates a paint which repeats an image.
+    ///
+    /// # Arguments
+    ///
+    /// * `image` - The SiImage to repeat.
+    #[wasm_bindgen]
+    pub fn pattern(image: &SiImage) -> SiPaint {
+        SiPaint {
+            kind: PaintKind::Pattern(image.rgba_image()),
+            stops: Vec::new(),
+        }
+    }
+
+    /// Adds a color stop to a gradient, like [`SiPaint::with_stop`], with the color in CSS
+    /// syntax.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SiError::Color`] if the color is not valid.
+    #[wasm_bindgen(js_name = "withStop")]
+    pub fn js_with_stop(&self, offset: f32, color: &str) -> Result<SiPaint, SiError> {
+        Ok(self.with_stop(offset, SiColor::parse(color)?))
+    }
+}
+
+impl SiPaint {
+    /// Creates a paint with a single color.
+    ///
+    /// # Arguments
+    ///
+    /// * `color` - The color of the paint.
+    pub fn solid(color: SiColor) -> SiPaint {
+        SiPaint {
+            kind: PaintKind::Solid(color.into()),
+            stops: Vec::new(),
+        }
+    }
+
+    /// Adds a color stop to a gradient.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - Where the color is along the gradient, from 0 (the start) to 1 (the end).
+    ///   Stops at offsets which aren't numbers are ignored.
+    /// * `color` - The color at the stop.
+    ///
+    /// # Returns
+    ///
+    /// A new SiPaint, with the stop added.
+    pub fn with_stop(&self, offset: f32, color: SiColor) -> SiPaint {
+        let mut paint = self.clone();
+        if offset.is_nan() {
+            return paint;
+        }
+        let offset = offset.clamp(0.0, 1.0);
+        let index = paint.stops.partition_point(|(o, _)| *o <= offset);
+        paint.stops.insert(index, (offset, color.into()));
+        paint
+    }
+
+    /// Gets the color of the paint at a position relative to its origin.
+    pub(crate) fn color_at(&self, x: f32, y: f32) -> Rgba<u8> {
+        match &self.kind {
+            PaintKind::Solid(color) => *color,
+            PaintKind::LinearGradient { start, end } => {
+                let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+                let length = dx * dx + dy * dy;
+                let t = if length > 0.0 {
+                    ((x - start.0) * dx + (y - start.1) * dy) / length
+                } else {
+                    0.0
+                };
+                self.gradient_color(t)
+            }
+            PaintKind::RadialGradient { center, radius } => {
+                let distance = ((x - center.0).powi(2) + (y - center.1).powi(2)).sqrt();
+                let t = if *radius > 0.0 {
+                    distance / radius
+                } else {
+                    1.0
+                };
+                self.gradient_color(t)
+            }
+            PaintKind::Pattern(image) => {
+                let (width, height) = image.dimensions();
+                if width == 0 || height == 0 {
+                    return Rgba([0, 0, 0, 0]);
+                }
+                let px = (x.floor() as i64).rem_euclid(width as i64) as u32;
+                let py = (y.floor() as i64).rem_euclid(height as i64) as u32;
+                *image.get_pixel(px, py)
+            }
+        }
+    }
+
+    /// Interpolates between the stops of a gradient, extending the first and last colors.
+    fn gradient_color(&self, t: f32) -> Rgba<u8> {
+        // Positions which aren't numbers (e.g. from a NaN gradient point) take the first color
+        let t = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) };
+        let (Some(first), Some(last)) = (self.stops.first(), self.stops.last()) else {
+            return Rgba([0, 0, 0, 0]);
+        };
+        if t <= first.0 {
+            return first.1;
+        }
+        if t >= last.0 {
+            return last.1;
+        }
+        let index = self.stops.partition_point(|(o, _)| *o <= t).max(1);
+        let ((o0, c0), (o1, c1)) = (self.stops[index - 1], self.stops[index]);
+        let f = if o1 > o0 { (t - o0) / (o1 - o0) } else { 0.0 };
+        Rgba(std::array::from_fn(|i| {
+            (c0[i] as f32 + (c1[i] as f32 - c0[i] as f32) * f).round() as u8
+        }))
+    }
+}
+
+impl From<SiColor> for SiPaint {
+    fn from(color: SiColor) -> SiPaint {
+        SiPaint::solid(color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nan_offsets_and_positions_do_not_panic() {
+        let paint = SiPaint::linear_gradient(0.0, 0.0, 10.0, 0.0)
+            .with_stop(f32::NAN, SiColor::WHITE)
+            .with_stop(0.0, SiColor::BLACK)
+            .with_stop(1.0, SiColor::WHITE);
+        assert_eq!(paint.stops.len(), 2);
+        assert_eq!(paint.color_at(f32::NAN, 0.0), Rgba([0, 0, 0, 255]));
+        assert_eq!(paint.color_at(5.0, 0.0), Rgba([128, 128, 128, 255]));
+
+        let paint = SiPaint::linear_gradient(f32::NAN, 0.0, 10.0, 0.0)
+            .with_stop(0.0, SiColor::BLACK)
+            .with_stop(1.0, SiColor::WHITE);
+        assert_eq!(paint.color_at(5.0, 0.0), Rgba([0, 0, 0, 255]));
+    }
+}
@@ -1,21 +1,19 @@
+use crate::error::*;
 use crate::image::*;
 
+/// The values passed to a preset, keyed by name.
+pub type PresetValues = std::collections::HashMap<String, Box<dyn std::any::Any>>;
+
+/// The callback run when a preset is loaded.
+pub type PresetCallback = Box<dyn Fn(&mut SiImage, PresetValues) -> Result<SiImage, SiError>>;
+
 /// Represents a Preset
 pub struct SiPreset {
-    pub cb: Box<
-        dyn Fn(&mut SiImage, std::collections::HashMap<String, Box<dyn std::any::Any>>) -> SiImage,
-    >,
+    pub cb: PresetCallback,
 }
 
 impl SiPreset {
-    pub fn new(
-        cb: Box<
-            dyn Fn(
-                &mut SiImage,
-                std::collections::HashMap<String, Box<dyn std::any::Any>>,
-            ) -> SiImage,
-        >,
-    ) -> Box<SiPreset> {
-        Box::new(SiPreset { cb: Box::new(cb) })
+    pub fn new(cb: PresetCallback) -> Box<SiPreset> {
+        Box::new(SiPreset { cb })
     }
 }
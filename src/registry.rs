@@ -0,0 +1,253 @@
+use ttf_parser::name_id;
+use wasm_bindgen::prelude::*;
+
+use crate::error::*;
+use crate::font::*;
+
+/// A font registered in a [`FontRegistry`], or any other font looked up by family name,
+/// weight and style.
+#[derive(Clone)]
+pub(crate) struct RegisteredFont<T = SiFont> {
+    pub family: String,
+    pub weight: u16,
+    pub italic: bool,
+    pub font: T,
+}
+
+/// Represents a collection of fonts, looked up by family name, weight and style.
+#[wasm_bindgen]
+#[derive(Clone, Default)]
+pub struct FontRegistry {
+    fonts: Vec<RegisteredFont>,
+}
+
+#[wasm_bindgen]
+impl FontRegistry {
+    /// Creates a new, empty FontRegistry.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> FontRegistry {
+        Self::default()
+    }
+
+    /// Registers a font under the family, weight and style read from the font itself.
+    ///
+    /// The family is read from the `name` table, preferring the typographic family name.
+    /// The weight and style are read from the `OS/2` table, or guessed from the subfamily
+    /// name (e.g. "Bold Italic") for fonts without one. Variable fonts whose `wght` axis was
+    /// set with [`SiFont::with_variation`] are registered at that weight.
+    ///
+    /// # Arguments
+    ///
+    /// * `font` - The SiFont to register.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SiError::FontParse`] if the font has no readable family name.
+    #[wasm_bindgen]
+    pub fn register(&mut self, font: &SiFont) -> Result<(), SiError> {
+        let face = font.fonts[0]
+            .face()
+            .ok_or_else(|| SiError::FontParse(String::from("font could not be read")))?;
+        let family = family_name(&face)
+            .ok_or_else(|| SiError::FontParse(String::from("font has no family name")))?;
+        let (weight, italic) = face_style(&face);
+        // Variable fonts are registered at the weight they were set to
+        let weight = font.fonts[0]
+            .variations
+            .iter()
+            .find(|(tag, _)| tag == b"wght")
+            .map_or(weight, |(_, value)| value.round() as u16);
+        self.register_as(font, &family, weight, italic);
+        Ok(())
+    }
+
+    /// Registers a font under a family, weight and style.
+    ///
+    /// # Arguments
+    ///
+    /// * `font` - The SiFont to register.
+    /// * `family` - The name of the family.
+    /// * `weight` - The weight of the font, from 100 (thin) to 900 (black).
+    /// * `italic` - Whether the font is italic (or oblique).
+    #[wasm_bindgen(js_name = "registerAs")]
+    pub fn register_as(&mut self, font: &SiFont, family: &str, weight: u16, italic: bool) {
+        self.fonts.push(RegisteredFont {
+            family: family.to_string(),
+            weight,
+            italic,
+            font: font.clone(),
+        });
+    }
+
+    /// Gets the names of the registered families.
+    #[wasm_bindgen(getter)]
+    pub fn families(&self) -> Vec<String> {
+        family_names(&self.fonts)
+    }
+
+    /// Gets the font of a family which best matches a weight and style.
+    ///
+    /// Family names are compared case-insensitively. Fonts in the requested style are
+    /// preferred, and weights are matched like CSS does: lighter weights look for lighter
+    /// fonts first, bolder weights for bolder fonts first.
+    ///
+    /// # Arguments
+    ///
+    /// * `family` - The name of the family.
+    /// * `weight` - The weight, from 100 (thin) to 900 (black).
+    /// * `italic` - Whether the text is italic.
+    ///
+    /// # Returns
+    ///
+    /// The nearest match, or `None` if no font of the family is registered
+    #[wasm_bindgen]
+    pub fn get(&self, family: &str, weight: u16, italic: bool) -> Option<SiFont> {
+        best_match(&self.fonts, family, weight, italic).cloned()
+    }
+
+    /// Gets the font which best matches a query like `"Inter, 700, italic"`.
+    ///
+    /// The query is a family name, optionally followed by a weight (a number, or a keyword
+    /// such as `bold`) and a style (`italic`, `oblique` or `normal`), separated by commas.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The query.
+    ///
+    /// # Returns
+    ///
+    /// The nearest match, or `None` if no font of the family is registered
+    #[wasm_bindgen]
+    pub fn query(&self, query: &str) -> Option<SiFont> {
+        let mut parts = query.split(',').map(str::trim);
+        let family = parts.next()?;
+        let mut weight = 400;
+        let mut italic = false;
+        for part in parts {
+            let part = part.to_ascii_lowercase();
+            match part.as_str() {
+                "italic" | "oblique" => italic = true,
+                "normal" => {}
+                _ => weight = part.parse().unwrap_or_else(|_| parse_style(&part).0),
+            }
+        }
+        self.get(family, weight, italic)
+    }
+
+    /// Gets the regular, bold, italic and bold italic variants of a family, for use with
+    /// [`SiRichText::from_markup`](crate::SiRichText::from_markup).
+    ///
+    /// # Arguments
+    ///
+    /// * `family` - The name of the family.
+    ///
+    /// # Returns
+    ///
+    /// The nearest matches to each variant, or `None` if no font of the family is registered
+    #[wasm_bindgen]
+    pub fn family(&self, family: &str) -> Option<SiFontFamily> {
+        Some(
+            SiFontFamily::new(&self.get(family, 400, false)?)
+                .with_bold(&self.get(family, 700, false)?)
+                .with_italic(&self.get(family, 400, true)?)
+                .with_bold_italic(&self.get(family, 700, true)?),
+        )
+    }
+}
+
+/// Gets the names of the families of fonts, in the order they were first seen.
+pub(crate) fn family_names<T>(fonts: &[RegisteredFont<T>]) -> Vec<String> {
+    let mut families: Vec<String> = Vec::new();
+    for font in fonts {
+        if !families.contains(&font.family) {
+            families.push(font.family.clone());
+        }
+    }
+    families
+}
+
+/// Finds the font of a family which best matches a weight and style, as described in
+/// [`FontRegistry::get`].
+pub(crate) fn best_match<'a, T>(
+    fonts: &'a [RegisteredFont<T>],
+    family: &str,
+    weight: u16,
+    italic: bool,
+) -> Option<&'a T> {
+    fonts
+        .iter()
+        .filter(|font| font.family.eq_ignore_ascii_case(family.trim()))
+        .min_by_key(|font| (font.italic != italic, weight_distance(weight, font.weight)))
+        .map(|font| &font.font)
+}
+
+/// Reads the weight and style of a font from its `OS/2` table, or guesses them from its
+/// subfamily name for fonts without one.
+pub(crate) fn face_style(face: &ttf_parser::Face) -> (u16, bool) {
+    match face.tables().os2 {
+        Some(_) => (
+            face.weight().to_number(),
+            face.is_italic() || face.is_oblique(),
+        ),
+        None => {
+            let subfamily = face_name(face, name_id::TYPOGRAPHIC_SUBFAMILY)
+                .or_else(|| face_name(face, name_id::SUBFAMILY))
+                .unwrap_or_default();
+            parse_style(&subfamily)
+        }
+    }
+}
+
+/// Ranks how far a font's weight is from the desired weight, following the CSS font
+/// matching algorithm. Lower is better.
+fn weight_distance(desired: u16, weight: u16) -> (u8, u16) {
+    let (desired, weight) = (desired as i32, weight as i32);
+    let rank = if (400..=500).contains(&desired) {
+        // Weights up to 500 first, then lighter ones, then bolder ones
+        if (desired..=500).contains(&weight) {
+            (0, weight - desired)
+        } else if weight < desired {
+            (1, desired - weight)
+        } else {
+            (2, weight - desired)
+        }
+    } else if desired < 400 {
+        if weight <= desired {
+            (0, desired - weight)
+        } else {
+            (1, weight - desired)
+        }
+    } else if weight >= desired {
+        (0, weight - desired)
+    } else {
+        (1, desired - weight)
+    };
+    (rank.0, rank.1 as u16)
+}
+
+/// Guesses the weight and style from a style name like "Semi Bold Italic".
+fn parse_style(name: &str) -> (u16, bool) {
+    let name = name.to_ascii_lowercase().replace([' ', '-', '_'], "");
+    let italic = name.contains("italic") || name.contains("oblique");
+    // Longer names go first, so that e.g. "semibold" isn't taken for "bold"
+    let weights = [
+        ("extralight", 200),
+        ("ultralight", 200),
+        ("extrabold", 800),
+        ("ultrabold", 800),
+        ("semibold", 600),
+        ("demibold", 600),
+        ("hairline", 100),
+        ("medium", 500),
+        ("light", 300),
+        ("black", 900),
+        ("heavy", 900),
+        ("thin", 100),
+        ("bold", 700),
+    ];
+    let weight = weights
+        .iter()
+        .find(|(keyword, _)| name.contains(keyword))
+        .map_or(400, |(_, weight)| *weight);
+    (weight, italic)
+}
@@ -0,0 +1,159 @@
+use wasm_bindgen::prelude::*;
+
+use crate::color::*;
+use crate::error::*;
+use crate::font::*;
+use crate::image::*;
+use crate::layout::{self, StyledText, TextLayout, TextStyle};
+
+/// A piece of rich text, with its own font, scale and color.
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct TextSpan {
+    /// The text of the span.
+    #[wasm_bindgen(getter_with_clone)]
+    pub text: String,
+    /// The SiFont the span is rendered with.
+    #[wasm_bindgen(getter_with_clone)]
+    pub font: SiFont,
+    /// The scale the span is rendered at.
+    pub scale: f32,
+    /// The color of the span. Black when unset.
+    pub color: Option<SiColor>,
+    /// Extra tracking added after every glyph, in pixels. Uses the letter spacing from the
+    /// TextOptions when unset.
+    pub letter_spacing: Option<f32>,
+}
+
+#[wasm_bindgen]
+impl TextSpan {
+    /// Creates a new TextSpan, like [`TextSpan::new`], with the color in CSS syntax.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SiError::Color`] if the color is not valid.
+    #[wasm_bindgen(constructor)]
+    pub fn js_new(
+        text: &str,
+        font: &SiFont,
+        scale: f32,
+        color: Option<String>,
+    ) -> Result<TextSpan, SiError> {
+        Ok(TextSpan::new(text, font, scale, parse_optional(color)?))
+    }
+}
+
+impl TextSpan {
+    /// Creates a new TextSpan.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text of the span.
+    /// * `font` - The SiFont the span is rendered with.
+    /// * `scale` - The scale the span is rendered at.
+    /// * `color` - The color of the span, or `None` for black.
+    pub fn new(text: &str, font: &SiFont, scale: f32, color: Option<SiColor>) -> TextSpan {
+        TextSpan {
+            text: text.to_string(),
+            font: font.clone(),
+            scale,
+            color,
+            letter_spacing: None,
+        }
+    }
+}
+
+/// Represents text made of spans with different fonts, scales and colors.
+///
+/// The spans are laid out together, as a single piece of text: they are wrapped and aligned
+/// as a whole, and the spans on a line share its baseline.
+#[wasm_bindgen]
+#[derive(Clone, Default)]
+pub struct SiRichText {
+    spans: Vec<TextSpan>,
+}
+
+#[wasm_bindgen]
+impl SiRichText {
+    /// Creates a new, empty SiRichText.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> SiRichText {
+        Self::default()
+    }
+
+    /// Appends a span.
+    ///
+    /// # Arguments
+    ///
+    /// * `span` - The TextSpan to append.
+    ///
+    /// # Returns
+    ///
+    /// A new SiRichText, with the span after the spans of this one
+    #[wasm_bindgen(js_name = "withSpan")]
+    pub fn with_span(&self, span: &TextSpan) -> SiRichText {
+        let mut rich_text = self.clone();
+        rich_text.spans.push(span.clone());
+        rich_text
+    }
+
+    /// Gets the text of all of the spans, without their styles.
+    #[wasm_bindgen(getter)]
+    pub fn text(&self) -> String {
+        self.spans.iter().map(|span| span.text.as_str()).collect()
+    }
+
+    /// Measures the text without rendering it.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - The TextOptions the text would be laid out with.
+    ///
+    /// # Returns
+    ///
+    /// The size of the text
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SiError::Overflow`] if the text has more than `max_lines` lines and the
+    /// overflow policy is [`TextOverflow::Error`].
+    #[wasm_bindgen]
+    pub fn measure(&self, options: &TextOptions) -> Result<TextMetrics, SiError> {
+        let layout = self.layout(None, None, options)?;
+        Ok(TextMetrics::from(&layout))
+    }
+}
+
+impl SiRichText {
+    /// Gets the spans of the text.
+    pub fn spans(&self) -> &[TextSpan] {
+        &self.spans
+    }
+
+    /// Lays out the spans into lines, positioned relative to the top-left corner of its box.
+    pub(crate) fn layout(
+        &self,
+        max_width: Option<f32>,
+        max_height: Option<f32>,
+        options: &TextOptions,
+    ) -> Result<TextLayout, SiError> {
+        let mut spans = Vec::new();
+        let mut styles = Vec::new();
+        let mut offset = 0;
+        for span in &self.spans {
+            spans.push((offset, styles.len()));
+            styles.push(TextStyle {
+                font: span.font.clone(),
+                scale: span.scale,
+                letter_spacing: span.letter_spacing.unwrap_or(options.letter_spacing),
+                color: span.color.unwrap_or(SiColor::BLACK),
+            });
+            offset += span.text.len();
+        }
+        let text = StyledText {
+            text: self.text().into(),
+            spans,
+        };
+        layout::layout(&text, styles, max_width, max_height, options)
+    }
+}
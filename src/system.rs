@@ -0,0 +1,189 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::error::*;
+use crate::font::*;
+use crate::registry::{best_match, face_style, family_names, RegisteredFont};
+
+/// A face of an installed font, found by scanning the font directories.
+struct SystemFace {
+    path: PathBuf,
+    index: u32,
+}
+
+impl SiFont {
+    /// Loads an installed font by family name, weight and style.
+    /// **NOTE**: It doesn't work in WASM. Only for direct usage.
+    ///
+    /// The standard fontconfig directories are scanned the first time a font is looked up:
+    /// `/usr/share/fonts`, `/usr/local/share/fonts`, `$XDG_DATA_HOME/fonts` (or
+    /// `~/.local/share/fonts`) and `~/.fonts`. Only the names and styles of the fonts are
+    /// read while scanning; the matching font is read in full when it is loaded. Fonts are
+    /// matched like [`FontRegistry::get`](crate::FontRegistry::get) does.
+    ///
+    /// # Arguments
+    ///
+    /// * `family` - The name of the family, compared case-insensitively.
+    /// * `weight` - The weight, from 100 (thin) to 900 (black).
+    /// * `italic` - Whether the font should be italic.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SiError::FontNotFound`] if no installed font belongs to the family, or
+    /// [`SiError::Io`] if the matching font can't be read.
+    pub fn from_system(family: &str, weight: u16, italic: bool) -> Result<SiFont, SiError> {
+        let face = best_match(system_faces(), family, weight, italic)
+            .ok_or_else(|| SiError::FontNotFound(family.to_string()))?;
+        Self::from_vec_with_index(std::fs::read(&face.path)?, face.index)
+    }
+
+    /// Gets the names of the installed font families.
+    /// **NOTE**: It doesn't work in WASM. Only for direct usage.
+    pub fn system_families() -> Vec<String> {
+        let mut families = family_names(system_faces());
+        families.sort();
+        families
+    }
+}
+
+/// Scans the font directories once, remembering the names and styles of their faces.
+fn system_faces() -> &'static [RegisteredFont<SystemFace>] {
+    static FACES: OnceLock<Vec<RegisteredFont<SystemFace>>> = OnceLock::new();
+    FACES.get_or_init(|| {
+        let mut faces = Vec::new();
+        for dir in font_dirs() {
+            scan_dir(&dir, &mut faces);
+        }
+        faces
+    })
+}
+
+/// The directories fontconfig searches by default.
+fn font_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![
+        PathBuf::from("/usr/share/fonts"),
+        PathBuf::from("/usr/local/share/fonts"),
+    ];
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    match std::env::var_os("XDG_DATA_HOME") {
+        Some(data) => dirs.push(PathBuf::from(data).join("fonts")),
+        None => dirs.extend(home.iter().map(|home| home.join(".local/share/fonts"))),
+    }
+    dirs.extend(home.iter().map(|home| home.join(".fonts")));
+    dirs
+}
+
+/// Adds the faces of the fonts in a directory and its subdirectories.
+fn scan_dir(dir: &Path, faces: &mut Vec<RegisteredFont<SystemFace>>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        // Symlinked directories aren't followed, as they may link back to a parent
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            scan_dir(&path, faces);
+            continue;
+        }
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+        if !matches!(extension.as_deref(), Some("ttf" | "otf" | "ttc" | "otc")) {
+            continue;
+        }
+        let Ok(mut file) = File::open(&path) else {
+            continue;
+        };
+        for (index, offset) in face_offsets(&mut file).into_iter().enumerate() {
+            let Some((family, weight, italic)) = read_face(&mut file, offset) else {
+                continue;
+            };
+            faces.push(RegisteredFont {
+                family,
+                weight,
+                italic,
+                font: SystemFace {
+                    path: path.clone(),
+                    index: index as u32,
+                },
+            });
+        }
+    }
+}
+
+/// Reads `length` bytes of a file, from `offset`.
+fn read_at(file: &mut File, offset: u64, length: usize) -> Option<Vec<u8>> {
+    // Corrupt fonts shouldn't make us allocate more than the size of the file
+    if offset.checked_add(length as u64)? > file.metadata().ok()?.len() {
+        return None;
+    }
+    let mut data = vec![0; length];
+    file.seek(SeekFrom::Start(offset)).ok()?;
+    file.read_exact(&mut data).ok()?;
+    Some(data)
+}
+
+/// Reads a big-endian `u32` from `data`, at `offset`.
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes(bytes.try_into().ok()?))
+}
+
+/// Finds where the faces of a font file start: a single face at the start of the file, or
+/// each face of a collection.
+fn face_offsets(file: &mut File) -> Vec<u64> {
+    let Some(header) = read_at(file, 0, 12) else {
+        return Vec::new();
+    };
+    if &header[..4] != b"ttcf" {
+        return vec![0];
+    }
+    let count = u32_at(&header, 8).unwrap_or(0) as usize;
+    let offsets = read_at(file, 12, count.min(u16::MAX as usize) * 4).unwrap_or_default();
+    (0..offsets.len() / 4)
+        .filter_map(|i| u32_at(&offsets, i * 4).map(u64::from))
+        .collect()
+}
+
+/// Reads the family name, weight and style of the face starting at `offset`.
+///
+/// Fonts, especially CJK collections, can be tens of megabytes, so only the table directory
+/// and the few small tables needed to tell the face apart are read, not the glyphs.
+fn read_face(file: &mut File, offset: u64) -> Option<(String, u16, bool)> {
+    let header = read_at(file, offset, 12)?;
+    let count = u16::from_be_bytes([header[4], header[5]]) as usize;
+    let records = read_at(file, offset + 12, count * 16)?;
+    let mut tables: [Vec<u8>; 6] = Default::default();
+    for record in records.chunks_exact(16) {
+        let Some(slot) = [b"head", b"hhea", b"maxp", b"name", b"OS/2", b"post"]
+            .iter()
+            .position(|tag| record[..4] == tag[..])
+        else {
+            continue;
+        };
+        let (start, length) = (u32_at(record, 8)?, u32_at(record, 12)?);
+        tables[slot] = read_at(file, start as u64, length as usize)?;
+    }
+    let [head, hhea, maxp, name, os2, post] = &tables;
+    fn optional(table: &[u8]) -> Option<&[u8]> {
+        (!table.is_empty()).then_some(table)
+    }
+    let face = ttf_parser::Face::from_raw_tables(ttf_parser::RawFaceTables {
+        head,
+        hhea,
+        maxp,
+        name: optional(name),
+        os2: optional(os2),
+        post: optional(post),
+        ..Default::default()
+    })
+    .ok()?;
+    let (weight, italic) = face_style(&face);
+    Some((family_name(&face)?, weight, italic))
+}